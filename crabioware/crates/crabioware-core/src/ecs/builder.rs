@@ -1,28 +1,18 @@
-use core::cell::RefCell;
-
-use anymap::hashbrown::AnyMap;
-
-use super::core::{Component, ComponentMap, EntityId, EntityMap};
+use super::bundle::Bundle;
+use super::core::{Component, EntityId};
+use super::world::World;
 
 #[must_use = "Must call .build() to finish constructing the Entity"]
 pub struct EntityBuilder<'a> {
     entity: EntityId,
-    entities: &'a mut EntityMap,
-    components: &'a mut AnyMap,
+    world: &'a mut World,
     is_built: bool,
 }
 impl<'a> EntityBuilder<'a> {
-    // TODO: I think we need to Drop the entity_id if not build()
-
-    pub fn new(
-        entity: EntityId,
-        entities: &'a mut EntityMap,
-        components: &'a mut AnyMap,
-    ) -> EntityBuilder<'a> {
+    pub fn new(entity: EntityId, world: &'a mut World) -> EntityBuilder<'a> {
         EntityBuilder {
             entity,
-            entities,
-            components,
+            world,
             is_built: false,
         }
     }
@@ -31,8 +21,12 @@ impl<'a> EntityBuilder<'a> {
     where
         C: Component + 'static,
     {
-        let storage = self.components.get_mut::<ComponentMap<C>>().unwrap();
-        storage.insert(self.entity, RefCell::new(component));
+        self.world.insert_component(&self.entity, component);
+        self
+    }
+
+    pub fn with_bundle<B: Bundle>(&mut self, bundle: B) -> &mut Self {
+        bundle.insert_into(self);
         self
     }
 
@@ -49,6 +43,12 @@ impl<'a> EntityBuilder<'a> {
         self
     }
 
+    // Attach the entity to a parent (see `World::set_parent`)
+    pub fn child_of(&mut self, parent: EntityId) -> &mut Self {
+        self.world.set_parent(&self.entity, &parent);
+        self
+    }
+
     pub fn build(&mut self) -> EntityId {
         self.is_built = true;
         self.entity
@@ -57,8 +57,10 @@ impl<'a> EntityBuilder<'a> {
 
 impl<'a> Drop for EntityBuilder<'a> {
     fn drop(&mut self) {
+        // Destroy rather than just removing the key so any components
+        // added with `.with()` don't linger in their storage
         if !self.is_built {
-            self.entities.remove(self.entity);
+            self.world.destroy(&self.entity);
         }
     }
 }
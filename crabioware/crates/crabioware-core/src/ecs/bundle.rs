@@ -0,0 +1,177 @@
+// Bundles and prefabs for spawning entities
+//
+// A `Bundle` is a group of components added to an entity together. Every
+// component is a bundle, tuples of bundles are bundles, and structs of
+// bundles can `#[derive(Bundle)]`, so bundles nest,
+//
+//     #[derive(Bundle)]
+//     struct Ball {
+//         sprite: SpriteComponent,
+//         body: PhysicsBundle,
+//     }
+//     let ball = world.spawn(Ball::new());
+//
+// A `Prefab` is a bundle that can be created from just a position. `Prefabs`
+// maps names to prefabs so level data (e.g., Tiled object types) can spawn
+// entities without knowing their components.
+use hashbrown::HashMap;
+
+use super::builder::EntityBuilder;
+use super::core::{Component, EntityId};
+use super::world::World;
+use crate::types::{Number, Vector2D};
+
+pub trait Bundle: Sized {
+    fn insert_into(self, builder: &mut EntityBuilder);
+}
+
+impl<C: Component> Bundle for C {
+    fn insert_into(self, builder: &mut EntityBuilder) {
+        builder.with(self);
+    }
+}
+
+macro_rules! impl_bundle_for_tuple {
+  ($($name:ident $index:tt)*) => {
+      impl<$($name,)*> Bundle for ($($name,)*)
+      where $($name: Bundle,)*
+      {
+          fn insert_into(self, builder: &mut EntityBuilder) {
+              $(self.$index.insert_into(builder);)*
+          }
+      }
+  };
+}
+
+impl_bundle_for_tuple!(A 0);
+impl_bundle_for_tuple!(A 0 B 1);
+impl_bundle_for_tuple!(A 0 B 1 C 2);
+impl_bundle_for_tuple!(A 0 B 1 C 2 D 3);
+impl_bundle_for_tuple!(A 0 B 1 C 2 D 3 E 4);
+impl_bundle_for_tuple!(A 0 B 1 C 2 D 3 E 4 F 5);
+impl_bundle_for_tuple!(A 0 B 1 C 2 D 3 E 4 F 5 G 6);
+impl_bundle_for_tuple!(A 0 B 1 C 2 D 3 E 4 F 5 G 6 H 7);
+
+pub trait Prefab: Bundle {
+    fn at(position: Vector2D<Number>) -> Self;
+}
+
+type PrefabSpawner = fn(&mut World, Vector2D<Number>) -> EntityId;
+
+fn spawn_prefab<P: Prefab>(world: &mut World, position: Vector2D<Number>) -> EntityId {
+    world.spawn(P::at(position))
+}
+
+#[derive(Default)]
+pub struct Prefabs {
+    spawners: HashMap<&'static str, PrefabSpawner>,
+}
+impl Prefabs {
+    pub fn new() -> Prefabs {
+        Self::default()
+    }
+
+    pub fn with<P: Prefab>(mut self, name: &'static str) -> Self {
+        self.register::<P>(name);
+        self
+    }
+
+    pub fn register<P: Prefab>(&mut self, name: &'static str) -> &mut Self {
+        self.spawners.insert(name, spawn_prefab::<P>);
+        self
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.spawners.contains_key(name)
+    }
+
+    // Spawn the prefab registered under a name, if there is one
+    pub fn spawn(
+        &self,
+        world: &mut World,
+        name: &str,
+        position: Vector2D<Number>,
+    ) -> Option<EntityId> {
+        self.spawners
+            .get(name)
+            .map(|spawner| spawner(world, position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agb::fixnum::num;
+
+    use super::{Prefab, Prefabs};
+    use crate::ecs::{Bundle, Component, World};
+    use crate::types::{Number, Vector2D};
+
+    #[derive(Debug, PartialEq, Eq, Component)]
+    struct Position(Vector2D<Number>);
+
+    #[derive(Debug, PartialEq, Eq, Component)]
+    struct Health(u8);
+
+    #[derive(Debug, PartialEq, Eq, Component)]
+    struct Sprite(u8);
+
+    #[derive(Bundle)]
+    struct Body {
+        position: Position,
+        health: Health,
+    }
+
+    #[derive(Bundle)]
+    struct Crab {
+        body: Body,
+        sprite: Sprite,
+    }
+    impl Prefab for Crab {
+        fn at(position: Vector2D<Number>) -> Self {
+            Crab {
+                body: Body {
+                    position: Position(position),
+                    health: Health(3),
+                },
+                sprite: Sprite(1),
+            }
+        }
+    }
+
+    #[derive(Bundle)]
+    struct Marker(Sprite, (Health,));
+
+    #[test_case]
+    fn test_spawn_nested_bundles(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let crab = world.spawn(Crab::at(Vector2D::new(num!(1.), num!(2.))));
+        let marker = world.spawn(Marker(Sprite(2), (Health(1),)));
+        let tuple = world.spawn((Sprite(3), Position(Vector2D::new(num!(0.), num!(0.)))));
+
+        let (position, health, sprite) = world.get::<(&Position, &Health, &Sprite)>(&crab).unwrap();
+        assert_eq!(
+            (position.0, health.0, sprite.0),
+            (Vector2D::new(num!(1.), num!(2.)), 3, 1)
+        );
+        assert!(world.has::<Health>(&marker) && !world.has::<Position>(&marker));
+        assert!(world.has::<Position>(&tuple) && !world.has::<Health>(&tuple));
+    }
+
+    #[test_case]
+    fn test_prefabs(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let prefabs = Prefabs::new().with::<Crab>("Spawn");
+
+        let crab = prefabs
+            .spawn(&mut world, "Spawn", Vector2D::new(num!(8.), num!(16.)))
+            .unwrap();
+        assert_eq!(
+            world.entry::<&Position>(&crab).0,
+            Vector2D::new(num!(8.), num!(16.))
+        );
+        assert!(prefabs
+            .spawn(&mut world, "Ghost", Vector2D::new(num!(0.), num!(0.)))
+            .is_none());
+        assert_eq!(world.components::<&Sprite>().count(), 1);
+    }
+}
@@ -0,0 +1,158 @@
+// Deferred world changes
+//
+// Queries borrow the `World`, so systems can't create or destroy entities
+// while iterating over one. Instead they can queue up `Commands` that are
+// applied at a sync point (e.g., between `Schedule` stages, or by calling
+// `World::apply_commands`), in the order they were queued.
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use super::builder::EntityBuilder;
+use super::bundle::Bundle;
+use super::core::{Component, EntityId};
+use super::world::World;
+
+type Command = Box<dyn FnOnce(&mut World)>;
+
+#[derive(Default)]
+pub struct Commands {
+    queue: Vec<Command>,
+}
+impl Commands {
+    pub fn new() -> Commands {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    // Queue any change to the world
+    pub fn add<F>(&mut self, command: F) -> &mut Self
+    where
+        F: FnOnce(&mut World) + 'static,
+    {
+        self.queue.push(Box::new(command));
+        self
+    }
+
+    // Queue creation of an entity, adding components with the builder
+    pub fn spawn<F>(&mut self, build: F) -> &mut Self
+    where
+        F: FnOnce(&mut EntityBuilder) + 'static,
+    {
+        self.add(move |world| {
+            let mut builder = world.create();
+            build(&mut builder);
+            builder.build();
+        })
+    }
+
+    pub fn spawn_bundle<B>(&mut self, bundle: B) -> &mut Self
+    where
+        B: Bundle + 'static,
+    {
+        self.add(move |world| {
+            world.spawn(bundle);
+        })
+    }
+
+    pub fn destroy(&mut self, entity_id: EntityId) -> &mut Self {
+        self.add(move |world| world.destroy(&entity_id))
+    }
+
+    pub fn insert_component<C>(&mut self, entity_id: EntityId, component: C) -> &mut Self
+    where
+        C: Component,
+    {
+        self.add(move |world| {
+            world.insert_component(&entity_id, component);
+        })
+    }
+
+    pub fn remove_component<C>(&mut self, entity_id: EntityId) -> &mut Self
+    where
+        C: Component,
+    {
+        self.add(move |world| {
+            world.remove_component::<C>(&entity_id);
+        })
+    }
+
+    // Run all queued commands against the world, emptying the queue
+    pub fn apply(&mut self, world: &mut World) {
+        for command in self.queue.drain(..) {
+            command(world);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::Commands;
+    use crate::ecs::{Component, EntityId, World};
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Health(u8);
+    impl Component for Health {}
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Dead;
+    impl Component for Dead {}
+
+    #[test_case]
+    fn test_commands_during_iteration(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let a = world.create().with(Health(0)).build();
+        let b = world.create().with(Health(2)).build();
+
+        for (id, health) in world.components::<(EntityId, &Health)>() {
+            let mut commands = world.commands();
+            if health.0 == 0 {
+                commands.destroy(id);
+                commands.spawn(|entity| {
+                    entity.with(Health(5));
+                });
+            } else {
+                commands.insert_component(id, Dead);
+            }
+        }
+        // Nothing happens until commands are applied
+        assert!(world.is_alive(&a));
+        assert!(!world.has::<Dead>(&b));
+        assert_eq!(world.commands().len(), 3);
+
+        world.apply_commands();
+        assert!(!world.is_alive(&a));
+        assert!(world.has::<Dead>(&b));
+        let health: Vec<u8> = world.components::<&Health>().map(|h| h.0).collect();
+        assert_eq!(health.len(), 2);
+        assert!(health.contains(&5));
+        assert!(world.commands().is_empty());
+    }
+
+    #[test_case]
+    fn test_commands_apply_in_order(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let a = world.create().with(Health(1)).build();
+
+        let mut commands = Commands::new();
+        commands
+            .insert_component(a, Dead)
+            .remove_component::<Dead>(a)
+            .add(move |world| {
+                world.entry::<&mut Health>(&a).0 += 1;
+            });
+        commands.apply(&mut world);
+
+        assert!(commands.is_empty());
+        assert!(!world.has::<Dead>(&a));
+        assert_eq!(*world.entry::<&Health>(&a), Health(2));
+    }
+}
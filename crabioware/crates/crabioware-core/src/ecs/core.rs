@@ -1,17 +1,168 @@
 use core::any::Any;
-use core::cell::RefCell;
+use core::cell::{Cell, RefCell, RefMut};
 
+use hashbrown::HashMap;
 use slotmap::{new_key_type, SecondaryMap, SlotMap};
 
+use super::registry::Signature;
+
 new_key_type! { pub struct EntityId; }
 
-// TODO: derive this as trait
-// https://doc.rust-lang.org/stable/book/ch19-06-macros.html
-// e.g., https://github.com/leudz/shipyard/blob/master/shipyard_proc/src/component_expand.rs#L4
-// TODO: define a type for storage on this trait
-pub trait Component: Any + Sized {}
+// How a component is stored. Dense storage is indexed by entity slot, so it's
+// fast but as large as the highest slot in use. Sparse storage is a hash map,
+// which suits components only a few entities have (the player, markers, etc).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+    #[default]
+    Dense,
+    Sparse,
+}
+
+// Usually derived, e.g.,
+//
+//     #[derive(Component)]
+//     #[component(storage = "sparse")]
+//     struct HeadComponent;
+pub trait Component: Any + Sized {
+    const STORAGE: StorageKind = StorageKind::Dense;
+}
+
+// Primary entity storage, holding the signature of each entity's components
+pub type EntityMap = SlotMap<EntityId, Signature>;
+
+// World ticks count up once per frame (see `World::advance_tick`), wrapping
+// around, so they're compared relative to each other. A tick more than half
+// the range (over a year of frames) behind another looks like it's ahead.
+pub type Tick = u32;
+
+// Whether `tick` is later than `than`, allowing for the counter wrapping
+fn is_after(tick: Tick, than: Tick) -> bool {
+    tick.wrapping_sub(than) as i32 > 0
+}
+
+// When a component was added, and when it was last mutably borrowed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComponentTicks {
+    pub added: Tick,
+    pub changed: Tick,
+}
+impl ComponentTicks {
+    pub fn new(tick: Tick) -> ComponentTicks {
+        Self {
+            added: tick,
+            changed: tick,
+        }
+    }
+
+    pub fn is_added_after(&self, tick: Tick) -> bool {
+        is_after(self.added, tick)
+    }
+
+    pub fn is_changed_after(&self, tick: Tick) -> bool {
+        is_after(self.changed, tick)
+    }
+}
+
+// A component, along with its change detection ticks
+pub struct ComponentCell<C> {
+    pub component: RefCell<C>,
+    pub ticks: Cell<ComponentTicks>,
+}
+impl<C> ComponentCell<C> {
+    pub fn new(component: C, tick: Tick) -> ComponentCell<C> {
+        Self {
+            component: RefCell::new(component),
+            ticks: Cell::new(ComponentTicks::new(tick)),
+        }
+    }
+
+    // Mutably borrow the component, marking it as changed. Returns None if
+    // it's already borrowed.
+    pub fn borrow_mut(&self, tick: Tick) -> Option<RefMut<'_, C>> {
+        let component = self.component.try_borrow_mut().ok()?;
+        let mut ticks = self.ticks.get();
+        ticks.changed = tick;
+        self.ticks.set(ticks);
+        Some(component)
+    }
+
+    pub fn into_inner(self) -> C {
+        self.component.into_inner()
+    }
+}
+impl<C: Clone> ComponentCell<C> {
+    // Copy of the component, marked as added at `tick`
+    pub fn clone_at(&self, tick: Tick) -> ComponentCell<C> {
+        ComponentCell::new(self.component.borrow().clone(), tick)
+    }
+}
+
+// Storage for one type of component, laid out according to its `StorageKind`
+pub enum ComponentMap<C> {
+    // Slotmap "secondary map"
+    Dense(SecondaryMap<EntityId, ComponentCell<C>>),
+    Sparse(HashMap<EntityId, ComponentCell<C>>),
+}
+impl<C> ComponentMap<C> {
+    pub fn new(kind: StorageKind) -> ComponentMap<C> {
+        match kind {
+            StorageKind::Dense => ComponentMap::Dense(SecondaryMap::new()),
+            StorageKind::Sparse => ComponentMap::Sparse(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, entity_id: EntityId) -> Option<&ComponentCell<C>> {
+        match self {
+            ComponentMap::Dense(components) => components.get(entity_id),
+            ComponentMap::Sparse(components) => components.get(&entity_id),
+        }
+    }
+
+    pub fn insert(
+        &mut self,
+        entity_id: EntityId,
+        component: ComponentCell<C>,
+    ) -> Option<ComponentCell<C>> {
+        match self {
+            ComponentMap::Dense(components) => components.insert(entity_id, component),
+            ComponentMap::Sparse(components) => components.insert(entity_id, component),
+        }
+    }
+
+    pub fn remove(&mut self, entity_id: EntityId) -> Option<ComponentCell<C>> {
+        match self {
+            ComponentMap::Dense(components) => components.remove(entity_id),
+            ComponentMap::Sparse(components) => components.remove(&entity_id),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            ComponentMap::Dense(components) => components.len(),
+            ComponentMap::Sparse(components) => components.len(),
+        }
+    }
 
-// Primary entity storage
-pub type EntityMap = SlotMap<EntityId, ()>;
-// Slotmap "secondary map"
-pub type ComponentMap<C> = SecondaryMap<EntityId, RefCell<C>>;
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+impl<C: Clone> ComponentMap<C> {
+    // Copy of the storage, with every component marked as added at `tick`
+    pub fn clone_at(&self, tick: Tick) -> ComponentMap<C> {
+        match self {
+            ComponentMap::Dense(components) => ComponentMap::Dense(
+                components
+                    .iter()
+                    .map(|(id, cell)| (id, cell.clone_at(tick)))
+                    .collect(),
+            ),
+            ComponentMap::Sparse(components) => ComponentMap::Sparse(
+                components
+                    .iter()
+                    .map(|(id, cell)| (*id, cell.clone_at(tick)))
+                    .collect(),
+            ),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use core::fmt;
+
+use super::core::EntityId;
+
+// Reasons a view of an entity's components can't be borrowed. Component
+// type names are included so a panic says what went wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryError {
+    // Entity was destroyed (or never existed in this world)
+    DeadEntity(EntityId),
+    // Entity is alive but doesn't have the component
+    MissingComponent(EntityId, &'static str),
+    // Component was never registered with `World::register_component`
+    UnregisteredComponent(&'static str),
+    // Component is already mutably borrowed, or immutably borrowed when
+    // asking for a mutable borrow
+    AlreadyBorrowed(EntityId, &'static str),
+}
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::DeadEntity(id) => write!(f, "entity {:?} is not alive", id),
+            QueryError::MissingComponent(id, component) => {
+                write!(f, "entity {:?} has no {} component", id, component)
+            }
+            QueryError::UnregisteredComponent(component) => {
+                write!(f, "component {} is not registered", component)
+            }
+            QueryError::AlreadyBorrowed(id, component) => {
+                write!(f, "{} of entity {:?} is already borrowed", component, id)
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+// Typed event channels between systems
+//
+// Systems that detect something (a collision, a ball going out of bounds)
+// can send an event instead of acting on it directly, and any number of
+// other systems (scoring, audio, particles) can read it. Events last for
+// one frame: `World::clear_events` empties every channel at the start of
+// each frame, so a reader must run after the sender in the same frame
+// (e.g., in a later `Stage`) to see its events.
+use core::slice::Iter;
+
+use alloc::vec::Vec;
+
+pub struct Events<E> {
+    events: Vec<E>,
+}
+impl<E> Events<E> {
+    pub fn new() -> Events<E> {
+        Self { events: Vec::new() }
+    }
+
+    pub fn send(&mut self, event: E) {
+        self.events.push(event);
+    }
+
+    pub fn iter(&self) -> Iter<'_, E> {
+        self.events.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
+impl<E> Default for Events<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<'e, E> IntoIterator for &'e Events<E> {
+    type Item = &'e E;
+    type IntoIter = Iter<'e, E>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::ecs::{RunCondition, Schedule, Stage, World};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Scored(u8);
+
+    #[test_case]
+    fn test_send_read_clear(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        world.add_event::<Scored>();
+        world.send_event(Scored(1));
+        world.send_event(Scored(2));
+
+        let scored: Vec<Scored> = world.events::<Scored>().iter().copied().collect();
+        assert_eq!(scored, [Scored(1), Scored(2)]);
+
+        world.clear_events();
+        assert!(world.events::<Scored>().is_empty());
+    }
+
+    #[test_case]
+    fn test_events_last_one_frame(_gba: &mut agb::Gba) {
+        #[derive(Default)]
+        struct Total(u8);
+
+        fn send(world: &mut World) {
+            world.send_event(Scored(1));
+        }
+        fn read(world: &mut World) {
+            let scored: u8 = world.events::<Scored>().iter().map(|s| s.0).sum();
+            world.resource_mut::<Total>().unwrap().0 += scored;
+        }
+
+        let mut world = World::new();
+        world.add_event::<Scored>();
+        world.insert_resource(Total::default());
+        let mut schedule = Schedule::new()
+            .with_system(Stage::Simulate, send)
+            .with_system(Stage::Late, read)
+            .with_system_if(Stage::Input, RunCondition::EveryNFrames(2), read);
+
+        schedule.run(&mut world);
+        schedule.run(&mut world);
+        // Only the Late reader saw each frame's event
+        assert_eq!(world.resource::<Total>().unwrap().0, 2);
+        assert_eq!(world.events::<Scored>().len(), 1);
+    }
+
+    #[test_case]
+    fn test_add_event_twice_keeps_events(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        world.add_event::<Scored>();
+        world.send_event(Scored(1));
+        world.add_event::<Scored>();
+        assert_eq!(world.events::<Scored>().len(), 1);
+    }
+}
@@ -1,7 +1,35 @@
+use core::marker::PhantomData;
+
+use super::core::{Component, Tick};
+use super::registry::Signature;
+use super::world::World;
 use super::EntityId;
 
+// Filters narrow down the entities matched by a query, on top of the
+// components requested by the `View`. Any state a filter needs, such as
+// component signatures, is prepared once per query so each entity is
+// checked cheaply.
 pub trait EntityMapFilter {
-    fn filter(&self, entity: EntityId) -> bool;
+    type State: Copy + 'static;
+
+    fn prepare(&self, world: &World) -> Self::State;
+    fn filter(
+        &self,
+        state: Self::State,
+        world: &World,
+        entity: EntityId,
+        signature: Signature,
+    ) -> bool;
+}
+
+// The empty filter matches every entity
+impl EntityMapFilter for () {
+    type State = ();
+
+    fn prepare(&self, _: &World) -> Self::State {}
+    fn filter(&self, _: (), _: &World, _: EntityId, _: Signature) -> bool {
+        true
+    }
 }
 
 pub struct IsEntity {
@@ -13,7 +41,10 @@ impl IsEntity {
     }
 }
 impl EntityMapFilter for IsEntity {
-    fn filter(&self, entity: EntityId) -> bool {
+    type State = ();
+
+    fn prepare(&self, _: &World) -> Self::State {}
+    fn filter(&self, _: (), _: &World, entity: EntityId, _: Signature) -> bool {
         entity == self.entity
     }
 }
@@ -27,7 +58,320 @@ impl IsNotEntity {
     }
 }
 impl EntityMapFilter for IsNotEntity {
-    fn filter(&self, entity: EntityId) -> bool {
+    type State = ();
+
+    fn prepare(&self, _: &World) -> Self::State {}
+    fn filter(&self, _: (), _: &World, entity: EntityId, _: Signature) -> bool {
         entity != self.entity
     }
 }
+
+// Entity has a component, without borrowing it
+pub struct With<C> {
+    component: PhantomData<C>,
+}
+impl<C: Component> With<C> {
+    pub fn new() -> With<C> {
+        Self {
+            component: PhantomData,
+        }
+    }
+}
+impl<C: Component> Default for With<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<C: Component> EntityMapFilter for With<C> {
+    type State = Option<Signature>;
+
+    fn prepare(&self, world: &World) -> Self::State {
+        world.registry().signature::<C>()
+    }
+    fn filter(&self, state: Self::State, _: &World, _: EntityId, signature: Signature) -> bool {
+        state.is_some_and(|component| signature.contains(component))
+    }
+}
+
+// Entity does not have a component
+pub struct Without<C> {
+    component: PhantomData<C>,
+}
+impl<C: Component> Without<C> {
+    pub fn new() -> Without<C> {
+        Self {
+            component: PhantomData,
+        }
+    }
+}
+impl<C: Component> Default for Without<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<C: Component> EntityMapFilter for Without<C> {
+    type State = Option<Signature>;
+
+    fn prepare(&self, world: &World) -> Self::State {
+        world.registry().signature::<C>()
+    }
+    fn filter(&self, state: Self::State, _: &World, _: EntityId, signature: Signature) -> bool {
+        // Nothing can have an unregistered component
+        state.is_none_or(|component| !signature.intersects(component))
+    }
+}
+
+// Entity had a component added after a tick. By default that's during the
+// current tick, but a system that doesn't run every frame (e.g. a renderer)
+// can remember the tick it last ran and catch up with `since`.
+pub struct Added<C> {
+    since: Option<Tick>,
+    component: PhantomData<C>,
+}
+impl<C: Component> Added<C> {
+    pub fn new() -> Added<C> {
+        Self {
+            since: None,
+            component: PhantomData,
+        }
+    }
+
+    pub fn since(tick: Tick) -> Added<C> {
+        Self {
+            since: Some(tick),
+            component: PhantomData,
+        }
+    }
+}
+impl<C: Component> Default for Added<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<C: Component> EntityMapFilter for Added<C> {
+    type State = (Option<Signature>, Tick);
+
+    fn prepare(&self, world: &World) -> Self::State {
+        let since = self.since.unwrap_or(world.tick().wrapping_sub(1));
+        (world.registry().signature::<C>(), since)
+    }
+    fn filter(
+        &self,
+        (component, since): Self::State,
+        world: &World,
+        entity: EntityId,
+        signature: Signature,
+    ) -> bool {
+        component.is_some_and(|component| signature.contains(component))
+            && world
+                .component_ticks::<C>(&entity)
+                .is_some_and(|ticks| ticks.is_added_after(since))
+    }
+}
+
+// Entity had a component added or mutably borrowed after a tick. Like
+// `Added`, this defaults to the current tick.
+pub struct Changed<C> {
+    since: Option<Tick>,
+    component: PhantomData<C>,
+}
+impl<C: Component> Changed<C> {
+    pub fn new() -> Changed<C> {
+        Self {
+            since: None,
+            component: PhantomData,
+        }
+    }
+
+    pub fn since(tick: Tick) -> Changed<C> {
+        Self {
+            since: Some(tick),
+            component: PhantomData,
+        }
+    }
+}
+impl<C: Component> Default for Changed<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<C: Component> EntityMapFilter for Changed<C> {
+    type State = (Option<Signature>, Tick);
+
+    fn prepare(&self, world: &World) -> Self::State {
+        let since = self.since.unwrap_or(world.tick().wrapping_sub(1));
+        (world.registry().signature::<C>(), since)
+    }
+    fn filter(
+        &self,
+        (component, since): Self::State,
+        world: &World,
+        entity: EntityId,
+        signature: Signature,
+    ) -> bool {
+        component.is_some_and(|component| signature.contains(component))
+            && world
+                .component_ticks::<C>(&entity)
+                .is_some_and(|ticks| ticks.is_changed_after(since))
+    }
+}
+
+// Any of a tuple of filters, e.g. `Or((With::<A>::new(), With::<B>::new()))`
+pub struct Or<T>(pub T);
+
+macro_rules! impl_filter_for_tuple {
+  ($($name:ident $index:tt)*) => {
+      // Tuples of filters are an "AND" operation
+      impl<$($name,)*> EntityMapFilter for ($($name,)*)
+      where $($name: EntityMapFilter,)*
+      {
+          type State = ($($name::State,)*);
+
+          fn prepare(&self, world: &World) -> Self::State {
+              ($(self.$index.prepare(world),)*)
+          }
+          fn filter(&self, state: Self::State, world: &World, entity: EntityId, signature: Signature) -> bool {
+              $(self.$index.filter(state.$index, world, entity, signature))&&*
+          }
+      }
+
+      impl<$($name,)*> EntityMapFilter for Or<($($name,)*)>
+      where $($name: EntityMapFilter,)*
+      {
+          type State = ($($name::State,)*);
+
+          fn prepare(&self, world: &World) -> Self::State {
+              ($(self.0.$index.prepare(world),)*)
+          }
+          fn filter(&self, state: Self::State, world: &World, entity: EntityId, signature: Signature) -> bool {
+              $(self.0.$index.filter(state.$index, world, entity, signature))||*
+          }
+      }
+  };
+}
+
+impl_filter_for_tuple!(A 0);
+impl_filter_for_tuple!(A 0 B 1);
+impl_filter_for_tuple!(A 0 B 1 C 2);
+impl_filter_for_tuple!(A 0 B 1 C 2 D 3);
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{Added, Changed, IsNotEntity, Or, With, Without};
+    use crate::ecs::{Component, ComponentTicks, EntityId, World};
+
+    struct Tile(u8);
+    impl Component for Tile {}
+
+    struct Head;
+    impl Component for Head {}
+
+    struct Berry;
+    impl Component for Berry {}
+
+    struct Unregistered;
+    impl Component for Unregistered {}
+
+    fn world() -> (World, EntityId, EntityId, EntityId) {
+        let mut world = World::new();
+        let head = world.create().with(Tile(0)).with(Head).build();
+        let body = world.create().with(Tile(1)).build();
+        let berry = world.create().with(Tile(2)).with(Berry).build();
+        (world, head, body, berry)
+    }
+
+    fn tiles<F: super::EntityMapFilter>(world: &World, filter: &F) -> Vec<u8> {
+        let mut tiles: Vec<u8> = world.query::<&Tile, F>(filter).map(|t| t.0).collect();
+        tiles.sort();
+        tiles
+    }
+
+    #[test_case]
+    fn test_with(_gba: &mut agb::Gba) {
+        let (world, ..) = world();
+        assert_eq!(tiles(&world, &With::<Head>::new()), [0]);
+        assert_eq!(tiles(&world, &With::<Unregistered>::new()), []);
+    }
+
+    #[test_case]
+    fn test_without(_gba: &mut agb::Gba) {
+        let (world, ..) = world();
+        assert_eq!(tiles(&world, &Without::<Head>::new()), [1, 2]);
+        assert_eq!(
+            tiles(&world, &(Without::<Head>::new(), Without::<Berry>::new())),
+            [1]
+        );
+        assert_eq!(tiles(&world, &Without::<Unregistered>::new()), [0, 1, 2]);
+    }
+
+    #[test_case]
+    fn test_or(_gba: &mut agb::Gba) {
+        let (world, ..) = world();
+        assert_eq!(
+            tiles(&world, &Or((With::<Head>::new(), With::<Berry>::new()))),
+            [0, 2]
+        );
+        assert_eq!(
+            tiles(
+                &world,
+                &Or((With::<Unregistered>::new(), Without::<Berry>::new()))
+            ),
+            [0, 1]
+        );
+    }
+
+    #[test_case]
+    fn test_compose_with_entity_filter(_gba: &mut agb::Gba) {
+        let (world, _, body, _) = world();
+        assert_eq!(
+            tiles(&world, &(Without::<Head>::new(), IsNotEntity::new(body))),
+            [2]
+        );
+    }
+
+    #[test_case]
+    fn test_added(_gba: &mut agb::Gba) {
+        let (mut world, head, ..) = world();
+        assert_eq!(tiles(&world, &Added::<Tile>::new()), [0, 1, 2]);
+
+        world.advance_tick();
+        assert_eq!(tiles(&world, &Added::<Tile>::new()), []);
+        world.insert_component(&head, Berry);
+        assert_eq!(tiles(&world, &Added::<Berry>::new()), [0]);
+        assert_eq!(tiles(&world, &Added::<Unregistered>::new()), []);
+    }
+
+    #[test_case]
+    fn test_changed(_gba: &mut agb::Gba) {
+        let (mut world, _, body, _) = world();
+        let since = world.tick();
+        world.advance_tick();
+        assert_eq!(tiles(&world, &Changed::<Tile>::new()), []);
+
+        // Reading doesn't count as a change, but mutable borrows do
+        let _ = world.get::<&Tile>(&body).unwrap();
+        assert_eq!(tiles(&world, &Changed::<Tile>::new()), []);
+        world.get::<&mut Tile>(&body).unwrap().0 = 3;
+        assert_eq!(tiles(&world, &Changed::<Tile>::new()), [3]);
+
+        // Catch up on changes across several ticks
+        world.advance_tick();
+        assert_eq!(tiles(&world, &Changed::<Tile>::new()), []);
+        assert_eq!(tiles(&world, &Changed::<Tile>::since(since)), [3]);
+        assert_eq!(tiles(&world, &Changed::<Tile>::since(0)), [0, 2, 3]);
+    }
+
+    #[test_case]
+    fn test_ticks_wrap(_gba: &mut agb::Gba) {
+        // Added just after the counter wrapped, checked since just before
+        let ticks = ComponentTicks::new(1);
+        assert!(ticks.is_added_after(u32::MAX));
+        assert!(ticks.is_changed_after(u32::MAX - 5));
+        assert!(!ticks.is_changed_after(1));
+
+        let ticks = ComponentTicks::new(u32::MAX);
+        assert!(!ticks.is_added_after(2));
+    }
+}
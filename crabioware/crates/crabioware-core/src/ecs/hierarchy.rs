@@ -0,0 +1,211 @@
+// Parent/child relationships between entities
+//
+// An entity with a `Parent` is destroyed along with that parent, and its
+// `LocalPosition` is relative to the parent's `WorldPosition`. Use
+// `World::set_parent` (or `EntityBuilder::child_of`) rather than inserting
+// these components directly so both sides of the relationship stay in sync.
+use alloc::vec::Vec;
+
+use super::filter::Without;
+use super::world::World;
+use super::{Component, EntityId};
+use crate::types::{Number, Vector2D};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct Parent(pub EntityId);
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Component)]
+#[component(storage = "sparse")]
+pub struct Children(Vec<EntityId>);
+impl Children {
+    pub fn iter(&self) -> impl Iterator<Item = &EntityId> {
+        self.0.iter()
+    }
+
+    pub fn as_slice(&self) -> &[EntityId] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(super) fn push(&mut self, child: EntityId) {
+        if !self.0.contains(&child) {
+            self.0.push(child);
+        }
+    }
+
+    pub(super) fn remove(&mut self, child: &EntityId) {
+        self.0.retain(|id| id != child);
+    }
+}
+
+// Position relative to the parent, or to the world for entities without one
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Component)]
+pub struct LocalPosition(pub Vector2D<Number>);
+
+// Position in the world, updated from `LocalPosition` by
+// `system_propagate_positions`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Component)]
+pub struct WorldPosition(pub Vector2D<Number>);
+
+// Walk down from each root entity, adding each child's local offset to its
+// parent's world position. Children without a `LocalPosition` sit on top of
+// their parent. `WorldPosition` is only mutably borrowed (and so marked as
+// changed) when it actually moves.
+pub fn system_propagate_positions(world: &mut World) {
+    let mut stack: Vec<(EntityId, Vector2D<Number>)> = world
+        .query::<(EntityId, &LocalPosition), _>(&Without::<Parent>::new())
+        .map(|(id, local)| (id, local.0))
+        .collect();
+
+    while let Some((id, position)) = stack.pop() {
+        if !world.has::<WorldPosition>(&id) {
+            world.insert_component(&id, WorldPosition(position));
+        } else if world.entry::<&WorldPosition>(&id).0 != position {
+            world.entry::<&mut WorldPosition>(&id).0 = position;
+        }
+
+        if let Ok(Some(children)) = world.get::<Option<&Children>>(&id) {
+            for child in children.iter() {
+                let local = match world.get::<Option<&LocalPosition>>(child) {
+                    Ok(Some(local)) => local.0,
+                    _ => Vector2D::default(),
+                };
+                stack.push((*child, position + local));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agb::fixnum::num;
+
+    use super::{system_propagate_positions, Children, LocalPosition, Parent, WorldPosition};
+    use crate::ecs::{Changed, EntityId, World};
+    use crate::types::Vector2D;
+
+    fn family(world: &mut World) -> (EntityId, EntityId, EntityId) {
+        let crab = world
+            .create()
+            .with(LocalPosition(Vector2D::new(num!(10.), num!(20.))))
+            .build();
+        let claw = world
+            .create()
+            .with(LocalPosition(Vector2D::new(num!(-4.), num!(2.))))
+            .child_of(crab)
+            .build();
+        let pincer = world
+            .create()
+            .with(LocalPosition(Vector2D::new(num!(-1.), num!(0.))))
+            .child_of(claw)
+            .build();
+        (crab, claw, pincer)
+    }
+
+    #[test_case]
+    fn test_set_parent(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let (crab, claw, pincer) = family(&mut world);
+
+        assert_eq!(*world.entry::<&Parent>(&claw), Parent(crab));
+        assert_eq!(world.entry::<&Children>(&crab).as_slice(), [claw]);
+        assert!(world.is_ancestor(&crab, &pincer));
+        assert!(!world.is_ancestor(&pincer, &crab));
+
+        // Reparenting moves the child between both parents' children
+        world.set_parent(&pincer, &crab);
+        assert_eq!(world.entry::<&Children>(&crab).as_slice(), [claw, pincer]);
+        assert!(world.entry::<&Children>(&claw).is_empty());
+
+        world.remove_parent(&pincer);
+        assert!(!world.has::<Parent>(&pincer));
+        assert_eq!(world.entry::<&Children>(&crab).as_slice(), [claw]);
+    }
+
+    #[test_case]
+    fn test_destroy_descendants(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let (crab, claw, pincer) = family(&mut world);
+        let other = world
+            .create()
+            .with(LocalPosition(Vector2D::new(num!(0.), num!(0.))))
+            .build();
+
+        world.destroy(&claw);
+        assert!(world.is_alive(&crab));
+        assert!(!world.is_alive(&claw));
+        assert!(!world.is_alive(&pincer));
+        assert!(world.entry::<&Children>(&crab).is_empty());
+
+        let (crab, claw, pincer) = family(&mut world);
+        world.destroy(&crab);
+        assert!(!world.is_alive(&claw));
+        assert!(!world.is_alive(&pincer));
+        assert!(world.is_alive(&other));
+    }
+
+    #[test_case]
+    fn test_propagate_positions(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let (crab, claw, pincer) = family(&mut world);
+        let marker = world.create().child_of(pincer).build();
+
+        system_propagate_positions(&mut world);
+        assert_eq!(
+            world.entry::<&WorldPosition>(&crab).0,
+            Vector2D::new(num!(10.), num!(20.))
+        );
+        assert_eq!(
+            world.entry::<&WorldPosition>(&claw).0,
+            Vector2D::new(num!(6.), num!(22.))
+        );
+        assert_eq!(
+            world.entry::<&WorldPosition>(&pincer).0,
+            Vector2D::new(num!(5.), num!(22.))
+        );
+        assert_eq!(
+            world.entry::<&WorldPosition>(&marker).0,
+            Vector2D::new(num!(5.), num!(22.))
+        );
+
+        // Only entities that moved are marked as changed
+        world.advance_tick();
+        world.entry::<&mut LocalPosition>(&claw).0 = Vector2D::new(num!(4.), num!(2.));
+        system_propagate_positions(&mut world);
+        assert_eq!(
+            world.entry::<&WorldPosition>(&pincer).0,
+            Vector2D::new(num!(13.), num!(22.))
+        );
+        let changed = world
+            .query::<&WorldPosition, _>(&Changed::<WorldPosition>::new())
+            .count();
+        assert_eq!(changed, 3);
+    }
+
+    #[test_case]
+    fn test_register_hierarchy(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        world.register_hierarchy();
+        let crab = world
+            .create()
+            .with(LocalPosition(Vector2D::new(num!(10.), num!(20.))))
+            .build();
+        system_propagate_positions(&mut world);
+        assert!(world.registry().is_cloneable::<WorldPosition>());
+
+        let snapshot = world.snapshot();
+        world.entry::<&mut WorldPosition>(&crab).0 = Vector2D::new(num!(0.), num!(0.));
+        world.restore(&snapshot);
+        assert_eq!(
+            world.entry::<&WorldPosition>(&crab).0,
+            Vector2D::new(num!(10.), num!(20.))
+        );
+    }
+}
@@ -44,7 +44,7 @@
 // * Specs ECS
 //     * I liked the user experience of their entity builder pattern
 //     * This ECS stores components in a similar manner to Specs (doesn't use archetypes)
-//       but (as of writing) we don't use anything like a bitmask to accelerate
+//       and, like Specs, keeps a bitmask "signature" per entity to accelerate
 //       queries when components are sparsely populated.
 //     * https://github.com/amethyst/specs
 // * This blog post has great visuals and benchmarks to explain how Specs and Legion
@@ -54,13 +54,32 @@
 //     * https://rust-tutorials.github.io/entity-component-scrapyard/01-introduction/introduction.html
 //
 mod builder;
+mod bundle;
+mod commands;
 mod core;
+mod error;
+mod events;
 mod filter;
+mod hierarchy;
+mod query;
+mod registry;
+mod schedule;
+mod snapshot;
 mod view;
 mod world;
 
 pub use builder::EntityBuilder;
-pub use core::{Component, EntityId};
-pub use filter::{EntityMapFilter, IsEntity, IsNotEntity};
+pub use bundle::{Bundle, Prefab, Prefabs};
+pub use commands::Commands;
+pub use core::{Component, ComponentTicks, EntityId, StorageKind, Tick};
+pub use crabioware_derive::{Bundle, Component};
+pub use error::QueryError;
+pub use events::Events;
+pub use filter::{Added, Changed, EntityMapFilter, IsEntity, IsNotEntity, Or, With, Without};
+pub use hierarchy::{system_propagate_positions, Children, LocalPosition, Parent, WorldPosition};
+pub use query::{Candidates, Combinations, Components, Entries, Pairs, Query};
+pub use registry::{ComponentRegistry, Signature};
+pub use schedule::{RunCondition, Schedule, Stage, System};
+pub use snapshot::WorldSnapshot;
 pub use view::View;
 pub use world::World;
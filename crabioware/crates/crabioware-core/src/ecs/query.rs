@@ -0,0 +1,344 @@
+// Query iterators
+//
+// Systems query the world every frame, so queries are concrete iterator
+// types over the entity map rather than boxed trait objects, and never
+// allocate. Matching an entity is a single signature mask test (plus the
+// query's filter, if it has one) before its components are borrowed.
+use core::marker::PhantomData;
+use core::slice;
+
+use slotmap::basic::Iter;
+
+use super::core::{EntityId, EntityMap};
+use super::filter::EntityMapFilter;
+use super::registry::Signature;
+use super::view::View;
+use super::world::World;
+
+// Entities whose signature contains all of a view's components
+#[derive(Clone)]
+struct Matching<'w> {
+    entities: Iter<'w, EntityId, Signature>,
+    // None if any component is unregistered, so nothing can match
+    signature: Option<Signature>,
+}
+impl<'w> Matching<'w> {
+    fn new(entities: &'w EntityMap, signature: Option<Signature>) -> Matching<'w> {
+        Self {
+            entities: entities.iter(),
+            signature,
+        }
+    }
+}
+impl<'w> Iterator for Matching<'w> {
+    type Item = (EntityId, Signature);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let signature = self.signature?;
+        self.entities
+            .find(|(_, entity)| entity.contains(signature))
+            .map(|(id, entity)| (id, *entity))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.signature {
+            Some(_) => (0, self.entities.size_hint().1),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+// Components of every entity matching a view, see `World::components`
+pub struct Components<'w, V> {
+    world: &'w World,
+    matching: Matching<'w>,
+    view: PhantomData<V>,
+}
+impl<'w, V: View> Components<'w, V> {
+    pub(super) fn new(world: &'w World, entities: &'w EntityMap) -> Components<'w, V> {
+        Self {
+            world,
+            matching: Matching::new(entities, V::signature(world.registry())),
+            view: PhantomData,
+        }
+    }
+}
+impl<'w, V: View> Iterator for Components<'w, V> {
+    type Item = V::Result<'w>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (id, _) = self.matching.next()?;
+        Some(self.world.borrow_matched::<V>(id))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.matching.size_hint()
+    }
+}
+
+// Entities matching a view's signature and a filter
+struct Filtered<'w, 'f, F: EntityMapFilter> {
+    world: &'w World,
+    matching: Matching<'w>,
+    filter: &'f F,
+    state: F::State,
+}
+impl<'w, 'f, F: EntityMapFilter> Filtered<'w, 'f, F> {
+    fn new(
+        world: &'w World,
+        entities: &'w EntityMap,
+        signature: Option<Signature>,
+        filter: &'f F,
+    ) -> Filtered<'w, 'f, F> {
+        Self {
+            world,
+            matching: Matching::new(entities, signature),
+            filter,
+            state: filter.prepare(world),
+        }
+    }
+}
+impl<'w, 'f, F: EntityMapFilter> Clone for Filtered<'w, 'f, F> {
+    fn clone(&self) -> Self {
+        Self {
+            world: self.world,
+            matching: self.matching.clone(),
+            filter: self.filter,
+            state: self.state,
+        }
+    }
+}
+impl<'w, 'f, F: EntityMapFilter> Iterator for Filtered<'w, 'f, F> {
+    type Item = EntityId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (world, filter, state) = (self.world, self.filter, self.state);
+        self.matching
+            .find(|(id, signature)| filter.filter(state, world, *id, *signature))
+            .map(|(id, _)| id)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.matching.size_hint()
+    }
+}
+
+// Components of every entity matching a view and a filter, see `World::query`
+pub struct Query<'w, 'f, V, F: EntityMapFilter> {
+    world: &'w World,
+    filtered: Filtered<'w, 'f, F>,
+    view: PhantomData<V>,
+}
+impl<'w, 'f, V: View, F: EntityMapFilter> Query<'w, 'f, V, F> {
+    pub(super) fn new(
+        world: &'w World,
+        entities: &'w EntityMap,
+        filter: &'f F,
+    ) -> Query<'w, 'f, V, F> {
+        Self {
+            world,
+            filtered: Filtered::new(world, entities, V::signature(world.registry()), filter),
+            view: PhantomData,
+        }
+    }
+}
+impl<'w, 'f, V: View, F: EntityMapFilter> Iterator for Query<'w, 'f, V, F> {
+    type Item = V::Result<'w>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.filtered.next()?;
+        Some(self.world.borrow_matched::<V>(id))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.filtered.size_hint()
+    }
+}
+
+// Components of a list of entities, see `World::entries`
+pub struct Entries<'w, V> {
+    world: &'w World,
+    entity_ids: slice::Iter<'w, EntityId>,
+    view: PhantomData<V>,
+}
+impl<'w, V: View> Entries<'w, V> {
+    pub(super) fn new(world: &'w World, entity_ids: &'w [EntityId]) -> Entries<'w, V> {
+        Self {
+            world,
+            entity_ids: entity_ids.iter(),
+            view: PhantomData,
+        }
+    }
+}
+impl<'w, V: View> Iterator for Entries<'w, V> {
+    type Item = V::Result<'w>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entity_ids.next().map(|id| self.world.entry::<V>(id))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entity_ids.size_hint()
+    }
+}
+
+// Components of every unordered pair of entities matching a view and a
+// filter, see `World::combinations`. Pairs come in the same order as nested
+// loops, (0, 1), (0, 2), ..., (1, 2), ...
+pub struct Combinations<'w, 'f, V, F: EntityMapFilter> {
+    world: &'w World,
+    outer: Filtered<'w, 'f, F>,
+    // The current first entity, and the entities after it
+    inner: Option<(EntityId, Filtered<'w, 'f, F>)>,
+    view: PhantomData<V>,
+}
+impl<'w, 'f, V: View, F: EntityMapFilter> Combinations<'w, 'f, V, F> {
+    pub(super) fn new(
+        world: &'w World,
+        entities: &'w EntityMap,
+        filter: &'f F,
+    ) -> Combinations<'w, 'f, V, F> {
+        Self {
+            world,
+            outer: Filtered::new(world, entities, V::signature(world.registry()), filter),
+            inner: None,
+            view: PhantomData,
+        }
+    }
+}
+impl<'w, 'f, V: View, F: EntityMapFilter> Iterator for Combinations<'w, 'f, V, F> {
+    type Item = (V::Result<'w>, V::Result<'w>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((id_a, inner)) = &mut self.inner {
+                if let Some(id_b) = inner.next() {
+                    return Some((
+                        self.world.borrow_matched::<V>(*id_a),
+                        self.world.borrow_matched::<V>(id_b),
+                    ));
+                }
+            }
+            let id_a = self.outer.next()?;
+            self.inner = Some((id_a, self.outer.clone()));
+        }
+    }
+}
+
+// Components of every pair of different entities where the first matches
+// one view and filter and the second matches another, see `World::pairs`.
+// Unlike `Combinations` these are ordered, so an entity matching both sides
+// appears in two pairs, (a, b) and (b, a).
+pub struct Pairs<'w, 'f, A, B, FA: EntityMapFilter, FB: EntityMapFilter> {
+    world: &'w World,
+    outer: Filtered<'w, 'f, FA>,
+    // Every entity matching B, restarted for each entity matching A
+    all_b: Filtered<'w, 'f, FB>,
+    inner: Option<(EntityId, Filtered<'w, 'f, FB>)>,
+    views: PhantomData<(A, B)>,
+}
+impl<'w, 'f, A, B, FA, FB> Pairs<'w, 'f, A, B, FA, FB>
+where
+    A: View,
+    B: View,
+    FA: EntityMapFilter,
+    FB: EntityMapFilter,
+{
+    pub(super) fn new(
+        world: &'w World,
+        entities: &'w EntityMap,
+        filter_a: &'f FA,
+        filter_b: &'f FB,
+    ) -> Pairs<'w, 'f, A, B, FA, FB> {
+        Self {
+            world,
+            outer: Filtered::new(world, entities, A::signature(world.registry()), filter_a),
+            all_b: Filtered::new(world, entities, B::signature(world.registry()), filter_b),
+            inner: None,
+            views: PhantomData,
+        }
+    }
+}
+impl<'w, 'f, A, B, FA, FB> Iterator for Pairs<'w, 'f, A, B, FA, FB>
+where
+    A: View,
+    B: View,
+    FA: EntityMapFilter,
+    FB: EntityMapFilter,
+{
+    type Item = (A::Result<'w>, B::Result<'w>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((id_a, inner)) = &mut self.inner {
+                let id_a = *id_a;
+                if let Some(id_b) = inner.find(|id_b| *id_b != id_a) {
+                    return Some((
+                        self.world.borrow_matched::<A>(id_a),
+                        self.world.borrow_matched::<B>(id_b),
+                    ));
+                }
+            }
+            let id_a = self.outer.next()?;
+            self.inner = Some((id_a, self.all_b.clone()));
+        }
+    }
+}
+
+// Components of pairs of entities from a broad phase (e.g., entities with
+// overlapping bounding boxes), see `World::candidates`. Each pair is yielded
+// with the entity matching `A` first, whichever order it was listed in, and
+// pairs that don't match both views (or have a dead entity) are skipped.
+pub struct Candidates<'w, A, B> {
+    world: &'w World,
+    entities: &'w EntityMap,
+    pairs: slice::Iter<'w, (EntityId, EntityId)>,
+    signature_a: Option<Signature>,
+    signature_b: Option<Signature>,
+    views: PhantomData<(A, B)>,
+}
+impl<'w, A: View, B: View> Candidates<'w, A, B> {
+    pub(super) fn new(
+        world: &'w World,
+        entities: &'w EntityMap,
+        pairs: &'w [(EntityId, EntityId)],
+    ) -> Candidates<'w, A, B> {
+        Self {
+            world,
+            entities,
+            pairs: pairs.iter(),
+            signature_a: A::signature(world.registry()),
+            signature_b: B::signature(world.registry()),
+            views: PhantomData,
+        }
+    }
+
+    fn matches(&self, id: EntityId, signature: Option<Signature>) -> bool {
+        match (self.entities.get(id), signature) {
+            (Some(entity), Some(signature)) => entity.contains(signature),
+            _ => false,
+        }
+    }
+}
+impl<'w, A: View, B: View> Iterator for Candidates<'w, A, B> {
+    type Item = (A::Result<'w>, B::Result<'w>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (a, b) = *self.pairs.next()?;
+            let (id_a, id_b) = if a == b {
+                continue;
+            } else if self.matches(a, self.signature_a) && self.matches(b, self.signature_b) {
+                (a, b)
+            } else if self.matches(b, self.signature_a) && self.matches(a, self.signature_b) {
+                (b, a)
+            } else {
+                continue;
+            };
+            return Some((
+                self.world.borrow_matched::<A>(id_a),
+                self.world.borrow_matched::<B>(id_b),
+            ));
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// Component registry
+//
+// Each registered component is given a bit index, and each entity stores a
+// `Signature` with the bits of the components it has. Queries can then
+// build the signature of a `View` once and test every entity with a single
+// mask check instead of looking up each component storage per entity.
+use core::any::{type_name, TypeId};
+use core::ops::BitOr;
+
+use alloc::vec::Vec;
+use anymap::hashbrown::AnyMap;
+use hashbrown::HashMap;
+
+use super::core::{Component, ComponentMap, EntityId, Tick};
+
+// The GBA is a 32-bit machine, so keep signatures to a single word
+pub const MAX_COMPONENTS: usize = u32::BITS as usize;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Signature(u32);
+impl Signature {
+    pub const EMPTY: Signature = Signature(0);
+
+    fn from_bit(bit: usize) -> Signature {
+        Signature(1 << bit)
+    }
+
+    // True if every component in `other` is also in `self`
+    pub fn contains(&self, other: Signature) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn intersects(&self, other: Signature) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn insert(&mut self, other: Signature) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Signature) {
+        self.0 &= !other.0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    // Index of the lowest component bit
+    fn index(&self) -> usize {
+        self.0.trailing_zeros() as usize
+    }
+}
+impl BitOr for Signature {
+    type Output = Signature;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Signature(self.0 | rhs.0)
+    }
+}
+
+// Type erased function that drops an entity's component from one ComponentMap
+type ComponentRemover = fn(&mut AnyMap, EntityId);
+
+fn remove_component<C: Component>(components: &mut AnyMap, entity_id: EntityId) {
+    if let Some(storage) = components.get_mut::<ComponentMap<C>>() {
+        storage.remove(entity_id);
+    }
+}
+
+// Type erased function that copies one component's storage between
+// component maps (e.g., into or out of a snapshot), marking each copy as
+// added at a tick. Storage missing from `from` is copied as empty.
+type ComponentCloner = fn(from: &AnyMap, to: &mut AnyMap, tick: Tick);
+
+fn clone_components<C: Component + Clone>(from: &AnyMap, to: &mut AnyMap, tick: Tick) {
+    let storage = match from.get::<ComponentMap<C>>() {
+        Some(storage) => storage.clone_at(tick),
+        None => ComponentMap::<C>::new(C::STORAGE),
+    };
+    to.insert(storage);
+}
+
+#[derive(Default)]
+pub struct ComponentRegistry {
+    signatures: HashMap<TypeId, Signature>,
+    // One remover per registered component so we can clean up on destroy
+    removers: Vec<ComponentRemover>,
+    // Components that opted into `Clone` can be copied for snapshots
+    cloners: Vec<Option<ComponentCloner>>,
+    names: Vec<&'static str>,
+}
+impl ComponentRegistry {
+    pub fn new() -> ComponentRegistry {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.removers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.removers.is_empty()
+    }
+
+    pub fn is_registered<C: Component>(&self) -> bool {
+        self.signatures.contains_key(&TypeId::of::<C>())
+    }
+
+    // Register storage for a component, assigning it the next free bit.
+    // Returns the component's signature, even if it was already registered.
+    pub fn register<C: Component>(&mut self, components: &mut AnyMap) -> Signature {
+        if let Some(signature) = self.signature::<C>() {
+            return signature;
+        }
+        assert!(
+            self.len() < MAX_COMPONENTS,
+            "Cannot register more than {} component types",
+            MAX_COMPONENTS
+        );
+        let signature = Signature::from_bit(self.len());
+        self.signatures.insert(TypeId::of::<C>(), signature);
+        self.removers.push(remove_component::<C>);
+        self.cloners.push(None);
+        self.names.push(type_name::<C>());
+        components.insert(ComponentMap::<C>::new(C::STORAGE));
+        signature
+    }
+
+    // Register a component that can be copied into snapshots
+    pub fn register_cloneable<C: Component + Clone>(
+        &mut self,
+        components: &mut AnyMap,
+    ) -> Signature {
+        let signature = self.register::<C>(components);
+        self.cloners[signature.index()] = Some(clone_components::<C>);
+        signature
+    }
+
+    pub fn is_cloneable<C: Component>(&self) -> bool {
+        self.signature::<C>()
+            .is_some_and(|signature| self.cloners[signature.index()].is_some())
+    }
+
+    // Signature of a single component, or None if it hasn't been registered
+    pub fn signature<C: Component>(&self) -> Option<Signature> {
+        self.signatures.get(&TypeId::of::<C>()).copied()
+    }
+
+    // Remove all components in an entity's signature from their storages
+    pub fn remove_all(&self, components: &mut AnyMap, entity_id: EntityId, signature: Signature) {
+        for (bit, remove) in self.removers.iter().enumerate() {
+            if signature.contains(Signature::from_bit(bit)) {
+                remove(components, entity_id);
+            }
+        }
+    }
+
+    // Copy every component storage from one component map to another.
+    // Panics if any registered component isn't cloneable.
+    pub fn clone_all(&self, from: &AnyMap, to: &mut AnyMap, tick: Tick) {
+        for (clone, name) in self.cloners.iter().zip(self.names.iter()) {
+            let clone = clone.unwrap_or_else(|| {
+                panic!(
+                    "Component {} must be registered with register_cloneable_component",
+                    name
+                )
+            });
+            clone(from, to, tick);
+        }
+    }
+}
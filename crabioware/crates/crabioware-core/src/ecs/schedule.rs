@@ -0,0 +1,197 @@
+// System scheduling
+//
+// Systems are plain functions over the `World`. A `Schedule` groups them into
+// stages that always run in the same order each frame (e.g., read input,
+// move things, resolve collisions, then tally up the score), and within a
+// stage systems run in the order they were added. Each system can have a
+// `RunCondition` to skip frames, such as moving a snake every N frames.
+// Queued `Commands` are applied at the end of each stage.
+use alloc::vec::Vec;
+
+use super::world::World;
+
+pub type System = fn(&mut World);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Input,
+    Simulate,
+    Collide,
+    Late,
+}
+impl Stage {
+    const COUNT: usize = 4;
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum RunCondition {
+    Always,
+    // Run on frames that are a multiple of N (counting from 1)
+    EveryNFrames(u32),
+    If(fn(&World) -> bool),
+}
+impl RunCondition {
+    fn should_run(&self, frame: u32, world: &World) -> bool {
+        match self {
+            RunCondition::Always => true,
+            RunCondition::EveryNFrames(n) => *n != 0 && frame.is_multiple_of(*n),
+            RunCondition::If(condition) => condition(world),
+        }
+    }
+}
+
+struct ScheduledSystem {
+    system: System,
+    condition: RunCondition,
+}
+
+#[derive(Default)]
+pub struct Schedule {
+    frame: u32,
+    stages: [Vec<ScheduledSystem>; Stage::COUNT],
+}
+impl Schedule {
+    pub fn new() -> Schedule {
+        Self::default()
+    }
+
+    pub fn with_system(mut self, stage: Stage, system: System) -> Self {
+        self.add_system(stage, system);
+        self
+    }
+
+    pub fn with_system_if(mut self, stage: Stage, condition: RunCondition, system: System) -> Self {
+        self.add_system_if(stage, condition, system);
+        self
+    }
+
+    pub fn add_system(&mut self, stage: Stage, system: System) -> &mut Self {
+        self.add_system_if(stage, RunCondition::Always, system)
+    }
+
+    pub fn add_system_if(
+        &mut self,
+        stage: Stage,
+        condition: RunCondition,
+        system: System,
+    ) -> &mut Self {
+        self.stages[stage.index()].push(ScheduledSystem { system, condition });
+        self
+    }
+
+    // Number of frames run so far
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    // Run one frame of all systems, stage by stage
+    pub fn run(&mut self, world: &mut World) {
+        self.frame = self.frame.wrapping_add(1);
+        world.advance_tick();
+        world.clear_events();
+        for stage in self.stages.iter() {
+            for scheduled in stage.iter() {
+                if scheduled.condition.should_run(self.frame, world) {
+                    (scheduled.system)(world);
+                }
+            }
+            world.apply_commands();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{RunCondition, Schedule, Stage};
+    use crate::ecs::World;
+
+    #[derive(Default)]
+    struct Log(Vec<&'static str>);
+
+    fn input(world: &mut World) {
+        world.resource_mut::<Log>().unwrap().0.push("input");
+    }
+    fn simulate(world: &mut World) {
+        world.resource_mut::<Log>().unwrap().0.push("simulate");
+    }
+    fn late(world: &mut World) {
+        world.resource_mut::<Log>().unwrap().0.push("late");
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.insert_resource(Log::default());
+        world
+    }
+
+    #[test_case]
+    fn test_stage_order(_gba: &mut agb::Gba) {
+        let mut world = world();
+        let mut schedule = Schedule::new()
+            .with_system(Stage::Late, late)
+            .with_system(Stage::Simulate, simulate)
+            .with_system(Stage::Input, input)
+            .with_system(Stage::Simulate, late);
+
+        schedule.run(&mut world);
+        assert_eq!(
+            world.resource::<Log>().unwrap().0,
+            ["input", "simulate", "late", "late"]
+        );
+        assert_eq!(schedule.frame(), 1);
+    }
+
+    #[test_case]
+    fn test_every_n_frames(_gba: &mut agb::Gba) {
+        let mut world = world();
+        let mut schedule = Schedule::new()
+            .with_system(Stage::Input, input)
+            .with_system_if(Stage::Simulate, RunCondition::EveryNFrames(3), simulate);
+
+        for _ in 0..6 {
+            schedule.run(&mut world);
+        }
+        let log = &world.resource::<Log>().unwrap().0;
+        assert_eq!(log.iter().filter(|s| **s == "input").count(), 6);
+        assert_eq!(log.iter().filter(|s| **s == "simulate").count(), 2);
+        assert_eq!(log[3], "simulate");
+    }
+
+    #[test_case]
+    fn test_commands_applied_after_stage(_gba: &mut agb::Gba) {
+        fn queue(world: &mut World) {
+            world.commands().add(input);
+        }
+
+        let mut world = world();
+        let mut schedule = Schedule::new()
+            .with_system(Stage::Input, queue)
+            .with_system(Stage::Input, simulate)
+            .with_system(Stage::Late, late);
+        schedule.run(&mut world);
+        assert_eq!(
+            world.resource::<Log>().unwrap().0,
+            ["simulate", "input", "late"]
+        );
+    }
+
+    #[test_case]
+    fn test_run_if(_gba: &mut agb::Gba) {
+        fn is_empty(world: &World) -> bool {
+            world.resource::<Log>().unwrap().0.is_empty()
+        }
+
+        let mut world = world();
+        let mut schedule =
+            Schedule::new().with_system_if(Stage::Input, RunCondition::If(is_empty), input);
+        schedule.run(&mut world);
+        schedule.run(&mut world);
+        assert_eq!(world.resource::<Log>().unwrap().0, ["input"]);
+    }
+}
@@ -0,0 +1,145 @@
+// World snapshots
+//
+// `World::snapshot` copies every entity, component and cloneable resource so
+// `World::restore` can rewind to it later (e.g., to retry a micro-game from
+// its start state, or to test that a rollback replays deterministically).
+// Every component must opt into this by being registered with
+// `World::register_cloneable_component`, since restoring only some of an
+// entity's components would leave it in a state that never existed.
+// Resources inserted with `World::insert_cloneable_resource` are restored
+// too, while other resources (input, etc) are left alone. Queued commands
+// and events belong to the timeline being abandoned, so they're dropped.
+//
+// Restored components are marked as added at the tick they're restored, so
+// change detection sees everything as new. Entity IDs created after a
+// snapshot shouldn't be kept across a restore, as their slots may be reused.
+use core::cell::RefCell;
+
+use anymap::hashbrown::AnyMap;
+
+use super::core::EntityMap;
+
+pub struct WorldSnapshot {
+    pub(super) entities: EntityMap,
+    pub(super) components: AnyMap,
+    pub(super) resources: AnyMap,
+}
+
+// Type erased function that copies one resource between resource maps,
+// removing it from `to` if it's missing from `from`
+pub(super) type ResourceCloner = fn(from: &AnyMap, to: &mut AnyMap);
+
+pub(super) fn clone_resource<R: Clone + 'static>(from: &AnyMap, to: &mut AnyMap) {
+    match from.get::<RefCell<R>>() {
+        Some(resource) => {
+            to.insert(resource.clone());
+        }
+        None => {
+            to.remove::<RefCell<R>>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ecs::{Added, Component, World};
+
+    #[derive(Debug, Clone, PartialEq, Eq, Component)]
+    struct Position(i32);
+
+    #[derive(Debug, Clone, PartialEq, Eq, Component)]
+    #[component(storage = "sparse")]
+    struct Player;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Score(u8);
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Input(u8);
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.register_cloneable_component::<Position>();
+        world.register_cloneable_component::<Player>();
+        world.insert_cloneable_resource(Score(0));
+        world.insert_resource(Input(0));
+        world
+    }
+
+    #[test_case]
+    fn test_restore(_gba: &mut agb::Gba) {
+        let mut world = world();
+        let player = world.create().with(Position(1)).with(Player).build();
+        let other = world.create().with(Position(2)).build();
+        let snapshot = world.snapshot();
+
+        world.entry::<&mut Position>(&player).0 = 10;
+        world.remove_component::<Player>(&player);
+        world.destroy(&other);
+        let spawned = world.create().with(Position(3)).build();
+        world.resource_mut::<Score>().unwrap().0 = 5;
+        world.resource_mut::<Input>().unwrap().0 = 1;
+
+        world.advance_tick();
+        world.restore(&snapshot);
+
+        assert_eq!(world.entry::<&Position>(&player).0, 1);
+        assert!(world.has::<Player>(&player));
+        assert!(world.is_alive(&other));
+        assert_eq!(world.entry::<&Position>(&other).0, 2);
+        assert!(!world.is_alive(&spawned));
+        assert_eq!(world.components::<&Position>().count(), 2);
+        // Only cloneable resources are rewound
+        assert_eq!(*world.resource::<Score>().unwrap(), Score(0));
+        assert_eq!(*world.resource::<Input>().unwrap(), Input(1));
+        // Restored components look newly added
+        assert_eq!(
+            world
+                .query::<&Position, _>(&Added::<Position>::new())
+                .count(),
+            2
+        );
+
+        // Snapshots can be restored more than once
+        world.destroy(&player);
+        world.restore(&snapshot);
+        assert!(world.is_alive(&player));
+    }
+
+    #[test_case]
+    fn test_restore_later_components(_gba: &mut agb::Gba) {
+        #[derive(Clone, Component)]
+        struct Health(u8);
+
+        let mut world = world();
+        let snapshot = world.snapshot();
+        world.register_cloneable_component::<Health>();
+        world.create().with(Position(1)).with(Health(3)).build();
+        world.remove_resource::<Score>();
+
+        world.restore(&snapshot);
+        assert_eq!(world.components::<&Health>().count(), 0);
+        assert_eq!(world.components::<&Position>().count(), 0);
+        assert!(world.has_resource::<Score>());
+    }
+
+    #[test_case]
+    fn test_restore_drops_commands_and_events(_gba: &mut agb::Gba) {
+        let mut world = world();
+        world.add_event::<u8>();
+        let player = world.create().with(Position(1)).build();
+        let snapshot = world.snapshot();
+
+        world.commands().destroy(player).spawn(|builder| {
+            builder.with(Position(2));
+        });
+        world.send_event(7u8);
+        world.restore(&snapshot);
+
+        assert!(world.commands().is_empty());
+        assert!(world.events::<u8>().is_empty());
+        world.apply_commands();
+        assert!(world.is_alive(&player));
+        assert_eq!(world.components::<&Position>().count(), 1);
+    }
+}
@@ -1,24 +1,48 @@
 // Find and borrow component data by entity ID(s)
 use anymap::hashbrown::AnyMap;
+use core::any::type_name;
 use core::cell::{Ref, RefMut};
 
-use super::core::{Component, ComponentMap, EntityId};
+use super::core::{Component, ComponentCell, ComponentMap, EntityId, Tick};
+use super::error::QueryError;
+use super::registry::{ComponentRegistry, Signature};
 
 pub trait View {
     type Result<'r>;
 
-    fn borrow(id: EntityId, component_maps: &AnyMap) -> Self::Result<'_>;
-    fn filter(id: EntityId, component_maps: &AnyMap) -> bool;
+    // Mutable borrows mark components as changed at the given tick
+    fn borrow(
+        id: EntityId,
+        component_maps: &AnyMap,
+        tick: Tick,
+    ) -> Result<Self::Result<'_>, QueryError>;
+    // Components an entity must have to match this view. Returns None if
+    // any of them are not registered, in which case nothing can match.
+    fn signature(registry: &ComponentRegistry) -> Option<Signature>;
+}
+
+// Find the cell holding an entity's component, if it has one
+fn component_cell<C>(
+    id: EntityId,
+    component_maps: &AnyMap,
+) -> Result<Option<&ComponentCell<C>>, QueryError>
+where
+    C: Component + 'static,
+{
+    match component_maps.get::<ComponentMap<C>>() {
+        Some(component_map) => Ok(component_map.get(id)),
+        None => Err(QueryError::UnregisteredComponent(type_name::<C>())),
+    }
 }
 
 impl View for EntityId {
     type Result<'r> = EntityId;
 
-    fn borrow(id: EntityId, _: &AnyMap) -> Self::Result<'_> {
-        id
+    fn borrow(id: EntityId, _: &AnyMap, _: Tick) -> Result<Self::Result<'_>, QueryError> {
+        Ok(id)
     }
-    fn filter(_: EntityId, _: &AnyMap) -> bool {
-        true
+    fn signature(_: &ComponentRegistry) -> Option<Signature> {
+        Some(Signature::EMPTY)
     }
 }
 
@@ -28,19 +52,20 @@ where
 {
     type Result<'r> = Ref<'r, C>;
 
-    fn borrow(id: EntityId, component_maps: &AnyMap) -> Self::Result<'_> {
-        component_maps
-            .get::<ComponentMap<C>>()
-            .unwrap()
-            .get(id)
-            .unwrap()
-            .borrow()
+    fn borrow(
+        id: EntityId,
+        component_maps: &AnyMap,
+        _: Tick,
+    ) -> Result<Self::Result<'_>, QueryError> {
+        component_cell::<C>(id, component_maps)?
+            .ok_or(QueryError::MissingComponent(id, type_name::<C>()))?
+            .component
+            .try_borrow()
+            .map_err(|_| QueryError::AlreadyBorrowed(id, type_name::<C>()))
     }
 
-    fn filter(id: EntityId, component_maps: &AnyMap) -> bool {
-        component_maps
-            .get::<ComponentMap<C>>()
-            .is_some_and(|cm| cm.contains_key(id))
+    fn signature(registry: &ComponentRegistry) -> Option<Signature> {
+        registry.signature::<C>()
     }
 }
 
@@ -50,37 +75,72 @@ where
 {
     type Result<'r> = RefMut<'r, C>;
 
-    fn borrow(id: EntityId, component_maps: &AnyMap) -> Self::Result<'_> {
-        component_maps
-            .get::<ComponentMap<C>>()
-            .unwrap()
-            .get(id)
-            .unwrap()
-            .borrow_mut()
+    fn borrow(
+        id: EntityId,
+        component_maps: &AnyMap,
+        tick: Tick,
+    ) -> Result<Self::Result<'_>, QueryError> {
+        component_cell::<C>(id, component_maps)?
+            .ok_or(QueryError::MissingComponent(id, type_name::<C>()))?
+            .borrow_mut(tick)
+            .ok_or(QueryError::AlreadyBorrowed(id, type_name::<C>()))
     }
 
-    fn filter(id: EntityId, component_maps: &AnyMap) -> bool {
-        component_maps
-            .get::<ComponentMap<C>>()
-            .is_some_and(|cm| cm.contains_key(id))
+    fn signature(registry: &ComponentRegistry) -> Option<Signature> {
+        registry.signature::<C>()
     }
 }
 
-impl<C> View for Option<C>
+// Optional components are None if the entity doesn't have them, or if
+// they've never been registered
+impl<C> View for Option<&C>
 where
     C: Component + 'static,
 {
     type Result<'r> = Option<Ref<'r, C>>;
 
-    fn borrow(id: EntityId, component_maps: &AnyMap) -> Self::Result<'_> {
-        match component_maps.get::<ComponentMap<C>>() {
-            Some(component_map) => Some(component_map.get(id).unwrap().borrow()),
-            None => None,
+    fn borrow(
+        id: EntityId,
+        component_maps: &AnyMap,
+        _: Tick,
+    ) -> Result<Self::Result<'_>, QueryError> {
+        match component_cell::<C>(id, component_maps).unwrap_or(None) {
+            Some(cell) => cell
+                .component
+                .try_borrow()
+                .map(Some)
+                .map_err(|_| QueryError::AlreadyBorrowed(id, type_name::<C>())),
+            None => Ok(None),
         }
     }
 
-    fn filter(_: EntityId, _: &AnyMap) -> bool {
-        true
+    fn signature(_: &ComponentRegistry) -> Option<Signature> {
+        Some(Signature::EMPTY)
+    }
+}
+
+impl<C> View for Option<&mut C>
+where
+    C: Component + 'static,
+{
+    type Result<'r> = Option<RefMut<'r, C>>;
+
+    fn borrow(
+        id: EntityId,
+        component_maps: &AnyMap,
+        tick: Tick,
+    ) -> Result<Self::Result<'_>, QueryError> {
+        match component_cell::<C>(id, component_maps).unwrap_or(None) {
+            Some(cell) => cell
+                .borrow_mut(tick)
+                .map(Some)
+                .ok_or(QueryError::AlreadyBorrowed(id, type_name::<C>())),
+            None => Ok(None),
+        }
+    }
+
+    fn signature(_: &ComponentRegistry) -> Option<Signature> {
+        Some(Signature::EMPTY)
     }
 }
 
@@ -93,27 +153,22 @@ macro_rules! impl_view_for_tuple {
       {
           type Result<'r> = ($($name::Result<'r>,)*);
 
-          fn borrow(id: EntityId, component_maps: &AnyMap) -> Self::Result<'_> {
-              ($($name::borrow(id, component_maps),)*)
+          fn borrow(
+              id: EntityId,
+              component_maps: &AnyMap,
+              tick: Tick,
+          ) -> Result<Self::Result<'_>, QueryError> {
+              Ok(($($name::borrow(id, component_maps, tick)?,)*))
           }
 
           // This is an "AND" operation
-          fn filter(id: EntityId, component_maps: &AnyMap) -> bool {
-              match ($($name::filter(id, component_maps),)*) {
-                  ($(replace_expr!($name true),)*) => true,
-                  _ => false,
-              }
+          fn signature(registry: &ComponentRegistry) -> Option<Signature> {
+              Some(Signature::EMPTY $(| $name::signature(registry)?)*)
           }
       }
   };
 }
 
-macro_rules! replace_expr {
-    ($_t:tt $repl:expr) => {
-        $repl
-    };
-}
-
 impl_view_for_tuple!(A);
 impl_view_for_tuple!(A B);
 impl_view_for_tuple!(A B C);
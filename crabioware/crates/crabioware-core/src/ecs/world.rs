@@ -1,21 +1,39 @@
-use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::any::{type_name, TypeId};
+use core::cell::{Ref, RefCell, RefMut};
 
 use anymap::hashbrown::AnyMap;
-use itertools::Itertools;
+use hashbrown::HashMap;
 
 use super::builder::EntityBuilder;
-use super::core::{Component, ComponentMap, EntityId, EntityMap};
+use super::bundle::Bundle;
+use super::commands::Commands;
+use super::core::{
+    Component, ComponentCell, ComponentMap, ComponentTicks, EntityId, EntityMap, Tick,
+};
+use super::error::QueryError;
+use super::events::Events;
 use super::filter::EntityMapFilter;
+use super::hierarchy::{Children, LocalPosition, Parent, WorldPosition};
+use super::query::{Candidates, Combinations, Components, Entries, Pairs, Query};
+use super::registry::{ComponentRegistry, Signature};
+use super::snapshot::{clone_resource, ResourceCloner, WorldSnapshot};
 use super::view::View;
 
-pub type EntityView<'r, V> = Box<<V as View>::Result<'r>>;
-pub type ComponentView<'r, V> = Box<dyn Iterator<Item = <V as View>::Result<'r>> + 'r>;
-pub type CombinationComponentView<'r, V> =
-    Box<dyn Iterator<Item = (<V as View>::Result<'r>, <V as View>::Result<'r>)> + 'r>;
-
 pub struct World {
     entities: EntityMap,
     components: AnyMap,
+    registry: ComponentRegistry,
+    // Global, typed, singletons shared between systems (score, RNG, etc)
+    resources: AnyMap,
+    // Changes queued by systems until the next sync point
+    commands: RefCell<Commands>,
+    // Current frame, for change detection
+    tick: Tick,
+    // Empties each `Events<E>` channel (stored as resources) every frame
+    event_clearers: Vec<fn(&World)>,
+    // Resources that opted into `Clone` are copied into snapshots
+    resource_cloners: HashMap<TypeId, ResourceCloner>,
 }
 impl Default for World {
     fn default() -> Self {
@@ -28,113 +46,834 @@ impl World {
         Self {
             entities: EntityMap::with_key(),
             components: AnyMap::new(),
+            registry: ComponentRegistry::new(),
+            resources: AnyMap::new(),
+            commands: RefCell::new(Commands::new()),
+            // Start after 0, so "changed after tick 0" matches everything
+            tick: 1,
+            event_clearers: Vec::new(),
+            resource_cloners: HashMap::new(),
         }
     }
 
-    // TODO: move into ComponentRegistry() as part of ::new()
-    //       this can help us with e.g. bitmask creation
+    pub fn registry(&self) -> &ComponentRegistry {
+        &self.registry
+    }
+
+    pub fn tick(&self) -> Tick {
+        self.tick
+    }
+
+    // Start a new frame. Components added or mutably borrowed from now on
+    // are marked with the new tick.
+    pub fn advance_tick(&mut self) {
+        self.tick = self.tick.wrapping_add(1);
+    }
+
     pub fn register_component<T: Component>(&mut self) {
-        if !self.components.contains::<T>() {
-            self.components.insert(ComponentMap::<T>::default());
-        }
+        self.registry.register::<T>(&mut self.components);
+    }
+
+    // Register a component that can be copied into snapshots
+    pub fn register_cloneable_component<T: Component + Clone>(&mut self) {
+        self.registry.register_cloneable::<T>(&mut self.components);
     }
 
     pub fn create(&mut self) -> EntityBuilder {
-        let entity = self.entities.insert(());
-        let builder = EntityBuilder::new(entity, &mut self.entities, &mut self.components);
-        builder
+        let entity = self.entities.insert(Signature::EMPTY);
+        EntityBuilder::new(entity, self)
     }
 
+    // Create an entity from a bundle of components
+    pub fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityId {
+        self.create().with_bundle(bundle).build()
+    }
+
+    // Destroy an entity and, recursively, all of its children
     pub fn destroy(&mut self, entity_id: &EntityId) {
-        self.entities.remove(*entity_id);
+        self.remove_parent(entity_id);
+        let children = self.remove_component::<Children>(entity_id);
+        if let Some(signature) = self.entities.remove(*entity_id) {
+            self.registry
+                .remove_all(&mut self.components, *entity_id, signature);
+        }
+        for child in children.iter().flat_map(Children::iter) {
+            self.destroy(child);
+        }
+    }
+
+    // Attach a child to a parent, detaching it from any previous parent.
+    // Both entities must be alive.
+    pub fn set_parent(&mut self, child: &EntityId, parent: &EntityId) {
+        if !self.is_alive(child) || !self.is_alive(parent) {
+            return;
+        }
+        assert!(
+            !self.is_ancestor(child, parent),
+            "Cannot make an entity a child of itself or its descendants"
+        );
+        self.register_hierarchy();
+
+        self.remove_parent(child);
+        self.insert_component(child, Parent(*parent));
+        if !self.has::<Children>(parent) {
+            self.insert_component(parent, Children::default());
+        }
+        self.entry::<&mut Children>(parent).push(*child);
+    }
+
+    // Register the hierarchy and position components so they can always be
+    // snapshotted. `set_parent` does this itself, so only worlds that use
+    // positions without ever parenting an entity need to call it.
+    pub fn register_hierarchy(&mut self) {
+        self.register_cloneable_component::<Parent>();
+        self.register_cloneable_component::<Children>();
+        self.register_cloneable_component::<LocalPosition>();
+        self.register_cloneable_component::<WorldPosition>();
+    }
+
+    // Detach a child from its parent, making it a root entity
+    pub fn remove_parent(&mut self, child: &EntityId) {
+        if let Some(Parent(parent)) = self.remove_component::<Parent>(child) {
+            if let Ok(mut children) = self.get::<&mut Children>(&parent) {
+                children.remove(child);
+            }
+        }
+    }
+
+    // True if `ancestor` is `entity_id`, or its parent, grandparent, etc
+    pub fn is_ancestor(&self, ancestor: &EntityId, entity_id: &EntityId) -> bool {
+        let mut current = Some(*entity_id);
+        while let Some(id) = current {
+            if id == *ancestor {
+                return true;
+            }
+            current = self.get::<&Parent>(&id).ok().map(|parent| parent.0);
+        }
+        false
     }
 
     pub fn is_alive(&self, entity_id: &EntityId) -> bool {
         self.entities.contains_key(*entity_id)
     }
 
-    pub fn entry<V>(&self, entity_id: &EntityId) -> EntityView<'_, V>
+    // Add a component to a live entity, returning the component it replaced.
+    // Dead entities are ignored. Components are registered the first time
+    // they're inserted, if they haven't been already.
+    pub fn insert_component<C>(&mut self, entity_id: &EntityId, component: C) -> Option<C>
+    where
+        C: Component,
+    {
+        let signature = self.registry.register::<C>(&mut self.components);
+        let entity = self.entities.get_mut(*entity_id)?;
+        entity.insert(signature);
+        self.components
+            .get_mut::<ComponentMap<C>>()
+            .unwrap()
+            .insert(*entity_id, ComponentCell::new(component, self.tick))
+            .map(ComponentCell::into_inner)
+    }
+
+    // Take a component off of a live entity, if it has one
+    pub fn remove_component<C>(&mut self, entity_id: &EntityId) -> Option<C>
+    where
+        C: Component,
+    {
+        let signature = self.registry.signature::<C>()?;
+        let entity = self.entities.get_mut(*entity_id)?;
+        entity.remove(signature);
+        self.components
+            .get_mut::<ComponentMap<C>>()?
+            .remove(*entity_id)
+            .map(ComponentCell::into_inner)
+    }
+
+    pub fn has<C>(&self, entity_id: &EntityId) -> bool
+    where
+        C: Component,
+    {
+        match (
+            self.registry.signature::<C>(),
+            self.entities.get(*entity_id),
+        ) {
+            (Some(signature), Some(entity)) => entity.contains(signature),
+            _ => false,
+        }
+    }
+
+    // When an entity's component was added and last mutably borrowed
+    pub fn component_ticks<C>(&self, entity_id: &EntityId) -> Option<ComponentTicks>
+    where
+        C: Component,
+    {
+        self.components
+            .get::<ComponentMap<C>>()?
+            .get(*entity_id)
+            .map(|cell| cell.ticks.get())
+    }
+
+    // Store a resource, returning any previous resource of the same type
+    pub fn insert_resource<R: 'static>(&mut self, resource: R) -> Option<R> {
+        self.resources
+            .insert(RefCell::new(resource))
+            .map(RefCell::into_inner)
+    }
+
+    // Store a resource that's copied into snapshots
+    pub fn insert_cloneable_resource<R: Clone + 'static>(&mut self, resource: R) -> Option<R> {
+        self.resource_cloners
+            .insert(TypeId::of::<R>(), clone_resource::<R>);
+        self.insert_resource(resource)
+    }
+
+    pub fn remove_resource<R: 'static>(&mut self) -> Option<R> {
+        self.resources
+            .remove::<RefCell<R>>()
+            .map(RefCell::into_inner)
+    }
+
+    pub fn has_resource<R: 'static>(&self) -> bool {
+        self.resources.contains::<RefCell<R>>()
+    }
+
+    // Resources are borrowed like components, so systems can hold one
+    // while iterating over a query
+    pub fn resource<R: 'static>(&self) -> Option<Ref<'_, R>> {
+        self.resources.get::<RefCell<R>>().map(RefCell::borrow)
+    }
+
+    pub fn resource_mut<R: 'static>(&self) -> Option<RefMut<'_, R>> {
+        self.resources.get::<RefCell<R>>().map(RefCell::borrow_mut)
+    }
+
+    // Queue changes to apply later, e.g. while iterating over a query
+    pub fn commands(&self) -> RefMut<'_, Commands> {
+        self.commands.borrow_mut()
+    }
+
+    // Sync point to apply all queued commands
+    pub fn apply_commands(&mut self) {
+        let mut commands = self.commands.take();
+        commands.apply(self);
+    }
+
+    // Add a channel for events of type `E`, if there isn't one already
+    pub fn add_event<E: 'static>(&mut self) {
+        if !self.has_resource::<Events<E>>() {
+            self.insert_resource(Events::<E>::new());
+            self.event_clearers.push(clear_events::<E>);
+        }
+    }
+
+    // Events can be sent while iterating over a query. Panics if the
+    // channel hasn't been added.
+    pub fn send_event<E: 'static>(&self, event: E) {
+        self.resource_mut::<Events<E>>()
+            .unwrap_or_else(|| panic!("Events<{}> have not been added", type_name::<E>()))
+            .send(event);
+    }
+
+    // Read this frame's events. Panics if the channel hasn't been added.
+    pub fn events<E: 'static>(&self) -> Ref<'_, Events<E>> {
+        self.resource::<Events<E>>()
+            .unwrap_or_else(|| panic!("Events<{}> have not been added", type_name::<E>()))
+    }
+
+    // Empty every event channel, ready for the next frame
+    pub fn clear_events(&mut self) {
+        for clear in self.event_clearers.iter() {
+            clear(self);
+        }
+    }
+
+    // Copy all entities, components and cloneable resources. Panics if any
+    // component isn't cloneable.
+    pub fn snapshot(&self) -> WorldSnapshot {
+        let mut components = AnyMap::new();
+        self.registry
+            .clone_all(&self.components, &mut components, self.tick);
+        let mut resources = AnyMap::new();
+        for clone in self.resource_cloners.values() {
+            clone(&self.resources, &mut resources);
+        }
+        WorldSnapshot {
+            entities: self.entities.clone(),
+            components,
+            resources,
+        }
+    }
+
+    // Rewind to a snapshot. Components registered since the snapshot was
+    // taken are left empty, and queued commands and events are dropped.
+    pub fn restore(&mut self, snapshot: &WorldSnapshot) {
+        self.commands.take();
+        self.clear_events();
+        self.entities = snapshot.entities.clone();
+        self.registry
+            .clone_all(&snapshot.components, &mut self.components, self.tick);
+        for clone in self.resource_cloners.values() {
+            clone(&snapshot.resources, &mut self.resources);
+        }
+    }
+
+    // Borrow a view of one entity's components
+    pub fn get<V>(&self, entity_id: &EntityId) -> Result<V::Result<'_>, QueryError>
     where
         V: View,
     {
-        Box::new(V::borrow(*entity_id, &self.components))
+        if !self.is_alive(entity_id) {
+            return Err(QueryError::DeadEntity(*entity_id));
+        }
+        V::borrow(*entity_id, &self.components, self.tick)
     }
 
-    pub fn entries<'e, V>(&'e self, entity_ids: &'e [EntityId]) -> ComponentView<'e, V>
+    // Like `get`, but panics (with a message saying why) if it fails
+    pub fn entry<V>(&self, entity_id: &EntityId) -> V::Result<'_>
     where
         V: View,
     {
-        Box::new(
-            entity_ids
-                .into_iter()
-                .map(|id| V::borrow(*id, &self.components)),
-        )
+        self.get::<V>(entity_id)
+            .unwrap_or_else(|error| panic!("{}", error))
     }
 
-    fn filter<'f, V, F>(&'f self, entity_filter: &'f F) -> Box<dyn Iterator<Item = EntityId> + '_>
+    pub fn entries<'e, V>(&'e self, entity_ids: &'e [EntityId]) -> Entries<'e, V>
     where
         V: View,
-        F: EntityMapFilter,
     {
-        // this is where a bitmask might help? before going to archetype
-        Box::new(
-            self.entities
-                .keys()
-                .filter(|id| entity_filter.filter(*id))
-                .filter(|id| V::filter(*id, &self.components)),
-        )
+        Entries::new(self, entity_ids)
     }
 
-    pub fn query<'f, V, F>(&'f self, entity_filter: &'f F) -> ComponentView<V>
+    // Borrow a view of an entity a query matched. This can only fail if
+    // a component is already borrowed elsewhere.
+    pub(super) fn borrow_matched<V>(&self, entity_id: EntityId) -> V::Result<'_>
+    where
+        V: View,
+    {
+        V::borrow(entity_id, &self.components, self.tick)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn query<'w, 'f, V, F>(&'w self, entity_filter: &'f F) -> Query<'w, 'f, V, F>
     where
         V: View,
         F: EntityMapFilter,
     {
-        Box::new(
-            self.filter::<V, F>(entity_filter)
-                .map(|id| V::borrow(id, &self.components)),
-        )
+        Query::new(self, &self.entities, entity_filter)
     }
 
-    pub fn combinations<V>(&self) -> CombinationComponentView<V>
+    // Every unordered pair of entities matching a view
+    pub fn combinations<V>(&self) -> Combinations<'_, 'static, V, ()>
     where
         V: View,
     {
-        let entities = self
-            .entities
-            .keys()
-            .tuple_combinations()
-            .filter(|(id_a, id_b)| {
-                V::filter(*id_a, &self.components) && V::filter(*id_b, &self.components)
-            });
-
-        Box::new(entities.map(|(id_a, id_b)| {
-            (
-                V::borrow(id_a, &self.components),
-                V::borrow(id_b, &self.components),
-            )
-        }))
+        Combinations::new(self, &self.entities, &())
     }
 
-    fn filter_components<V>(&self) -> Box<dyn Iterator<Item = EntityId> + '_>
+    // Every unordered pair of entities matching a view and a filter
+    pub fn query_combinations<'w, 'f, V, F>(
+        &'w self,
+        entity_filter: &'f F,
+    ) -> Combinations<'w, 'f, V, F>
     where
         V: View,
+        F: EntityMapFilter,
     {
-        // this is where a bitmask might help? before going to archetype
-        Box::new(
-            self.entities
-                .keys()
-                .filter(|id| V::filter(*id, &self.components)),
-        )
+        Combinations::new(self, &self.entities, entity_filter)
     }
 
-    pub fn components<V>(&self) -> ComponentView<V>
+    // Every pair of different entities matching two views, e.g., balls
+    // against paddles
+    pub fn pairs<A, B>(&self) -> Pairs<'_, 'static, A, B, (), ()>
+    where
+        A: View,
+        B: View,
+    {
+        Pairs::new(self, &self.entities, &(), &())
+    }
+
+    // Every pair of different entities matching two views and filters
+    pub fn query_pairs<'w, 'f, A, B, FA, FB>(
+        &'w self,
+        filter_a: &'f FA,
+        filter_b: &'f FB,
+    ) -> Pairs<'w, 'f, A, B, FA, FB>
+    where
+        A: View,
+        B: View,
+        FA: EntityMapFilter,
+        FB: EntityMapFilter,
+    {
+        Pairs::new(self, &self.entities, filter_a, filter_b)
+    }
+
+    // Pairs from a broad phase that match two views
+    pub fn candidates<'w, A, B>(&'w self, pairs: &'w [(EntityId, EntityId)]) -> Candidates<'w, A, B>
+    where
+        A: View,
+        B: View,
+    {
+        Candidates::new(self, &self.entities, pairs)
+    }
+
+    pub fn components<V>(&self) -> Components<'_, V>
     where
         V: View,
     {
-        Box::new(
-            self.filter_components::<V>()
-                .map(|id| V::borrow(id, &self.components)),
-        )
+        Components::new(self, &self.entities)
+    }
+}
+
+fn clear_events<E: 'static>(world: &World) {
+    if let Some(mut events) = world.resource_mut::<Events<E>>() {
+        events.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agb::timer::Divider;
+    use alloc::boxed::Box;
+    use core::cell::RefCell;
+
+    use super::World;
+    use crate::ecs::core::{Component, ComponentMap, EntityId};
+    use crate::ecs::error::QueryError;
+    use crate::ecs::filter::{With, Without};
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Position(i32);
+    impl Component for Position {}
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Health(u8);
+    impl Component for Health {}
+
+    fn stored<C: Component>(world: &World) -> usize {
+        world
+            .components
+            .get::<ComponentMap<C>>()
+            .map_or(0, ComponentMap::len)
+    }
+
+    #[test_case]
+    fn test_destroy_removes_components(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let a = world.create().with(Position(1)).with(Health(3)).build();
+        let b = world.create().with(Position(2)).build();
+
+        world.destroy(&a);
+
+        assert!(!world.is_alive(&a));
+        assert_eq!(stored::<Position>(&world), 1);
+        assert_eq!(stored::<Health>(&world), 0);
+
+        let positions: alloc::vec::Vec<i32> =
+            world.components::<&Position>().map(|p| p.0).collect();
+        assert_eq!(positions, [2]);
+        assert_eq!(world.components::<&Health>().count(), 0);
+        assert!(world.is_alive(&b));
+    }
+
+    #[test_case]
+    fn test_destroy_reused_slot(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let a = world.create().with(Position(1)).with(Health(3)).build();
+        world.destroy(&a);
+
+        // New entity may reuse the slot of the dead one, but must not see its data
+        let b = world.create().with(Position(2)).build();
+        assert_eq!(world.components::<&Health>().count(), 0);
+        assert_eq!(
+            world
+                .components::<(EntityId, &Position)>()
+                .map(|(id, p)| (id, p.0))
+                .collect::<alloc::vec::Vec<_>>(),
+            [(b, 2)]
+        );
+    }
+
+    #[test_case]
+    fn test_destroy_twice(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let a = world.create().with(Position(1)).build();
+        world.destroy(&a);
+        world.destroy(&a);
+        assert_eq!(stored::<Position>(&world), 0);
+    }
+
+    #[test_case]
+    fn test_unbuilt_builder_removes_components(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        {
+            let mut builder = world.create();
+            builder.with(Position(1)).with(Health(3));
+        }
+        assert_eq!(world.components::<EntityId>().count(), 0);
+        assert_eq!(stored::<Position>(&world), 0);
+        assert_eq!(stored::<Health>(&world), 0);
+    }
+
+    #[test_case]
+    fn test_signatures(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let a = world.create().with(Position(1)).with(Health(3)).build();
+        let b = world.create().with(Health(4)).build();
+
+        let position = world.registry.signature::<Position>().unwrap();
+        let health = world.registry.signature::<Health>().unwrap();
+        assert_ne!(position, health);
+        assert_eq!(world.entities[a], position | health);
+        assert_eq!(world.entities[b], health);
+
+        let found: alloc::vec::Vec<EntityId> = world
+            .components::<(EntityId, &Position, &Health)>()
+            .map(|(id, ..)| id)
+            .collect();
+        assert_eq!(found, [a]);
+        assert_eq!(world.components::<&Health>().count(), 2);
+    }
+
+    #[test_case]
+    fn test_unregistered_component_matches_nothing(_gba: &mut agb::Gba) {
+        struct Unregistered;
+        impl Component for Unregistered {}
+
+        let mut world = World::new();
+        world.create().with(Position(1)).build();
+        assert_eq!(world.components::<&Unregistered>().count(), 0);
+        assert_eq!(world.components::<(&Position, &Unregistered)>().count(), 0);
+        assert_eq!(world.combinations::<&Unregistered>().count(), 0);
+    }
+
+    #[test_case]
+    fn test_derive_registers_on_insert(_gba: &mut agb::Gba) {
+        #[derive(crate::ecs::Component)]
+        #[component(storage = "sparse")]
+        struct Player(u8);
+
+        let mut world = World::new();
+        let a = world.create().with(Player(1)).with(Position(2)).build();
+
+        assert!(world.registry.is_registered::<Player>());
+        assert!(matches!(
+            world.components.get::<ComponentMap<Player>>(),
+            Some(ComponentMap::Sparse(_))
+        ));
+        assert!(matches!(
+            world.components.get::<ComponentMap<Position>>(),
+            Some(ComponentMap::Dense(_))
+        ));
+        assert_eq!(world.entry::<&Player>(&a).0, 1);
+        assert_eq!(world.components::<(&Player, &Position)>().count(), 1);
+
+        world.destroy(&a);
+        assert_eq!(stored::<Player>(&world), 0);
+    }
+
+    #[test_case]
+    fn test_insert_component(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let a = world.create().with(Position(1)).build();
+        assert!(!world.has::<Health>(&a));
+
+        assert_eq!(world.insert_component(&a, Health(3)), None);
+        assert!(world.has::<Health>(&a));
+        assert_eq!(*world.entry::<&Health>(&a), Health(3));
+        assert_eq!(world.components::<(&Position, &Health)>().count(), 1);
+
+        // Replaces existing component
+        assert_eq!(world.insert_component(&a, Health(5)), Some(Health(3)));
+        assert_eq!(*world.entry::<&Health>(&a), Health(5));
+        assert_eq!(stored::<Health>(&world), 1);
+    }
+
+    #[test_case]
+    fn test_insert_component_dead_entity(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let a = world.create().with(Position(1)).build();
+        world.destroy(&a);
+
+        assert_eq!(world.insert_component(&a, Health(3)), None);
+        assert!(!world.has::<Health>(&a));
+        assert_eq!(stored::<Health>(&world), 0);
+    }
+
+    #[test_case]
+    fn test_remove_component(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let a = world.create().with(Position(1)).with(Health(3)).build();
+
+        assert_eq!(world.remove_component::<Health>(&a), Some(Health(3)));
+        assert_eq!(world.remove_component::<Health>(&a), None);
+        assert!(!world.has::<Health>(&a));
+        assert!(world.has::<Position>(&a));
+        assert_eq!(world.components::<&Health>().count(), 0);
+        assert_eq!(world.components::<&Position>().count(), 1);
+        assert_eq!(stored::<Health>(&world), 0);
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Score(u8);
+
+    #[test_case]
+    fn test_resources(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        assert!(world.resource::<Score>().is_none());
+        assert!(!world.has_resource::<Score>());
+
+        assert_eq!(world.insert_resource(Score(0)), None);
+        assert!(world.has_resource::<Score>());
+        world.resource_mut::<Score>().unwrap().0 += 2;
+        assert_eq!(*world.resource::<Score>().unwrap(), Score(2));
+
+        assert_eq!(world.insert_resource(Score(5)), Some(Score(2)));
+        assert_eq!(world.remove_resource::<Score>(), Some(Score(5)));
+        assert!(world.resource::<Score>().is_none());
+    }
+
+    #[test_case]
+    fn test_resource_while_querying(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        world.insert_resource(Score(0));
+        world.create().with(Health(3)).build();
+        world.create().with(Health(4)).build();
+
+        let mut score = world.resource_mut::<Score>().unwrap();
+        for health in world.components::<&Health>() {
+            score.0 += health.0;
+        }
+        assert_eq!(*score, Score(7));
+    }
+
+    #[test_case]
+    fn test_resources_separate_from_components(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        world.insert_resource(Health(9));
+        assert_eq!(world.components::<&Health>().count(), 0);
+        assert_eq!(stored::<Health>(&world), 0);
+    }
+
+    #[test_case]
+    fn test_get(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        // Registered, but not on `a`
+        world.register_component::<Health>();
+        let a = world.create().with(Position(1)).build();
+
+        let (id, position) = world.get::<(EntityId, &Position)>(&a).unwrap();
+        assert_eq!((id, position.0), (a, 1));
+        drop(position);
+
+        assert!(matches!(
+            world.get::<&Health>(&a),
+            Err(QueryError::MissingComponent(id, _)) if id == a
+        ));
+
+        world.destroy(&a);
+        assert!(matches!(
+            world.get::<&Position>(&a),
+            Err(QueryError::DeadEntity(id)) if id == a
+        ));
+    }
+
+    #[test_case]
+    fn test_get_unregistered(_gba: &mut agb::Gba) {
+        struct Unregistered;
+        impl Component for Unregistered {}
+
+        let mut world = World::new();
+        let a = world.create().with(Position(1)).build();
+        assert!(matches!(
+            world.get::<&Unregistered>(&a),
+            Err(QueryError::UnregisteredComponent(_))
+        ));
+        assert!(world.get::<Option<&Unregistered>>(&a).unwrap().is_none());
+    }
+
+    #[test_case]
+    fn test_get_already_borrowed(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let a = world.create().with(Position(1)).build();
+
+        assert!(matches!(
+            world.get::<(&mut Position, &Position)>(&a),
+            Err(QueryError::AlreadyBorrowed(id, _)) if id == a
+        ));
+        // Failed borrows are released
+        assert!(world.get::<&mut Position>(&a).is_ok());
+        // Shared borrows are fine
+        assert!(world.get::<(&Position, &Position)>(&a).is_ok());
+    }
+
+    #[test_case]
+    fn test_get_optional(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let a = world.create().with(Position(1)).build();
+        let b = world.create().with(Position(2)).with(Health(3)).build();
+
+        let (position, health) = world.get::<(&Position, Option<&Health>)>(&a).unwrap();
+        assert_eq!(position.0, 1);
+        assert!(health.is_none());
+        drop((position, health));
+
+        if let Some(mut health) = world.get::<Option<&mut Health>>(&b).unwrap() {
+            health.0 += 1;
+        }
+        assert_eq!(*world.entry::<&Health>(&b), Health(4));
+
+        let total: u8 = world
+            .components::<Option<&Health>>()
+            .map(|health| health.map_or(0, |h| h.0))
+            .sum();
+        assert_eq!(total, 4);
+    }
+
+    #[test_case]
+    fn test_register_twice_keeps_storage(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        world.create().with(Position(1)).build();
+        world.register_component::<Position>();
+        assert_eq!(stored::<Position>(&world), 1);
+    }
+
+    #[test_case]
+    fn test_component_ticks(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let a = world.create().with(Position(1)).build();
+        let added = world.tick();
+
+        world.advance_tick();
+        world.insert_component(&a, Health(3));
+        for mut position in world.components::<&mut Position>() {
+            position.0 += 1;
+        }
+
+        let position = world.component_ticks::<Position>(&a).unwrap();
+        assert_eq!(position.added, added);
+        assert_eq!(position.changed, world.tick());
+        let health = world.component_ticks::<Health>(&a).unwrap();
+        assert_eq!(health.added, world.tick());
+        assert!(world
+            .component_ticks::<Health>(&EntityId::default())
+            .is_none());
+    }
+
+    #[test_case]
+    fn test_combinations(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        for position in 0..4 {
+            world.create().with(Position(position)).build();
+        }
+        world.create().with(Health(1)).build();
+
+        let pairs: alloc::vec::Vec<(i32, i32)> = world
+            .combinations::<&Position>()
+            .map(|(a, b)| (a.0, b.0))
+            .collect();
+        assert_eq!(pairs, [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]);
+    }
+
+    #[test_case]
+    fn test_filtered_combinations_and_pairs(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let a = world.create().with(Position(0)).with(Health(1)).build();
+        let b = world.create().with(Position(1)).build();
+        let c = world.create().with(Position(2)).with(Health(2)).build();
+        let d = world.create().with(Position(3)).build();
+
+        let healthy: alloc::vec::Vec<(EntityId, EntityId)> = world
+            .query_combinations::<EntityId, _>(&With::<Health>::new())
+            .collect();
+        assert_eq!(healthy, [(a, c)]);
+
+        // Entities with health against those without, never with themselves
+        let pairs: alloc::vec::Vec<(EntityId, i32)> = world
+            .query_pairs::<(EntityId, &Health), &Position, _, _>(&(), &Without::<Health>::new())
+            .map(|((id, _), position)| (id, position.0))
+            .collect();
+        assert_eq!(pairs, [(a, 1), (a, 3), (c, 1), (c, 3)]);
+        assert_eq!(world.pairs::<&Position, &Position>().count(), 12);
+        assert_eq!(world.pairs::<&Health, &Position>().count(), 6);
+
+        // Broad phase candidates are flipped to match the views, and pairs
+        // that can't match are skipped
+        world.destroy(&d);
+        let candidates = [(b, a), (a, c), (b, b), (d, c), (b, c)];
+        let matched: alloc::vec::Vec<(EntityId, EntityId)> = world
+            .candidates::<(EntityId, &Health), (EntityId, &Position)>(&candidates)
+            .map(|((id_a, _), (id_b, _))| (id_a, id_b))
+            .collect();
+        assert_eq!(matched, [(a, b), (a, c), (c, b)]);
+    }
+
+    // Cycles taken to run `f`, counted by timer 2 cascading into timer 3
+    fn cycles(gba: &mut agb::Gba, f: impl FnOnce()) -> u32 {
+        let timers = gba.timers.timers();
+        let (mut low, mut high) = (timers.timer2, timers.timer3);
+        high.set_overflow_amount(0)
+            .set_cascade(true)
+            .set_enabled(true);
+        low.set_overflow_amount(0)
+            .set_divider(Divider::Divider1)
+            .set_enabled(true);
+        f();
+        low.set_enabled(false);
+        high.set_enabled(false);
+        (u32::from(high.value()) << 16) | u32::from(low.value())
+    }
+
+    // The query path before signatures, ticks and concrete iterator types:
+    // a boxed iterator over every entity, probing the component's map to
+    // see if it has one (the old `filter_components`), then boxed again to
+    // look it up a second time and borrow it (the old `components`)
+    fn baseline_filter_components<C: Component>(
+        world: &World,
+    ) -> Box<dyn Iterator<Item = EntityId> + '_> {
+        Box::new(world.entities.keys().filter(|id| {
+            world
+                .components
+                .get::<ComponentMap<C>>()
+                .is_some_and(|map| map.get(*id).is_some())
+        }))
+    }
+
+    fn baseline_components<'w, C: Component, R: 'w>(
+        world: &'w World,
+        borrow: fn(&'w RefCell<C>) -> R,
+    ) -> Box<dyn Iterator<Item = R> + 'w> {
+        Box::new(baseline_filter_components::<C>(world).map(move |id| {
+            let map = world.components.get::<ComponentMap<C>>().unwrap();
+            borrow(&map.get(id).unwrap().component)
+        }))
+    }
+
+    #[test_case]
+    fn test_query_benchmark(gba: &mut agb::Gba) {
+        let mut world = World::new();
+        for position in 0..32 {
+            world.create().with(Position(position)).build();
+        }
+        world.create().with(Position(0)).with(Health(1)).build();
+
+        // Lots of small queries, like many systems each touching a few entities
+        let frames = 60;
+        let baseline = cycles(gba, || {
+            for _ in 0..frames {
+                for _ in baseline_components::<Health, _>(&world, RefCell::borrow) {}
+                let positions = baseline_components::<Position, _>(&world, RefCell::borrow_mut);
+                for mut position in positions {
+                    position.0 += 1;
+                }
+            }
+        });
+        let concrete = cycles(gba, || {
+            for _ in 0..frames {
+                for _ in world.components::<&Health>() {}
+                for mut position in world.components::<&mut Position>() {
+                    position.0 += 1;
+                }
+            }
+        });
+        assert!(concrete < baseline);
     }
 }
@@ -1,6 +1,6 @@
 use super::games::Games;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum GameState {
     // Start screen / game selection
     Start(Games),
@@ -4,6 +4,8 @@
 #![cfg_attr(test, reexport_test_harness_main = "test_main")]
 #![cfg_attr(test, test_runner(agb::test_runner::test_runner))]
 extern crate alloc;
+// Lets `#[derive(Component)]` refer to this crate by name from within it
+extern crate self as crabioware_core;
 
 pub mod ecs;
 pub mod games;
@@ -0,0 +1,298 @@
+// Shared movement and collision components
+//
+// Micro-games build their moving entities from these rather than defining
+// their own location and velocity components,
+//
+//     world.spawn((
+//         Transform::centered(),
+//         Velocity::new(Vector2D { x: num!(0.5), y: num!(0.5) }),
+//         MaxSpeed::symmetric(num!(2.)),
+//         RigidBody::default(),
+//         Collider::new(Rect::new(Vector2D::default(), Vector2D { x: 8.into(), y: 8.into() })),
+//     ));
+//
+// and `system_integrate` moves them each frame. Entities the game moves
+// itself (e.g., paddles following input) are marked `Kinematic` so the
+// integrator leaves them alone, while they still collide with everything.
+use agb::display::{HEIGHT as GBA_HEIGHT, WIDTH as GBA_WIDTH};
+use agb::fixnum::num;
+
+use crate::ecs::{Component, Without, World};
+use crate::types::{Number, OrientedRect, Rect, RectMath, Vector2D};
+
+// Position (of the top left corner) and rotation, in screen pixels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Component)]
+pub struct Transform {
+    pub position: Vector2D<Number>,
+    pub angle: Number,
+}
+impl Transform {
+    pub fn at(position: Vector2D<Number>) -> Transform {
+        Self {
+            position,
+            angle: num!(0.),
+        }
+    }
+
+    pub fn centered() -> Transform {
+        Self::at(Vector2D {
+            x: Number::new(GBA_WIDTH / 2),
+            y: Number::new(GBA_HEIGHT / 2),
+        })
+    }
+}
+
+// Change per frame of a `Transform`, and of the velocity itself
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Component)]
+pub struct Velocity {
+    pub velocity: Vector2D<Number>,
+    pub acceleration: Vector2D<Number>,
+    pub rotation: Number,
+}
+impl Velocity {
+    pub fn new(velocity: Vector2D<Number>) -> Velocity {
+        Self {
+            velocity,
+            ..Default::default()
+        }
+    }
+
+    pub fn clamp(&mut self, max_speed: &MaxSpeed) {
+        self.velocity.x = self.velocity.x.clamp(max_speed.neg, max_speed.pos);
+        self.velocity.y = self.velocity.y.clamp(max_speed.neg, max_speed.pos);
+    }
+}
+
+// Limits on each axis of an entity's `Velocity`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct MaxSpeed {
+    pos: Number,
+    neg: Number,
+}
+impl MaxSpeed {
+    pub fn symmetric(pos: Number) -> MaxSpeed {
+        Self { pos, neg: -pos }
+    }
+}
+impl Default for MaxSpeed {
+    fn default() -> Self {
+        Self::symmetric(Number::new(2))
+    }
+}
+
+// How an entity responds to collisions. 1/mass is used for calculations, so
+// it's stored instead of mass, and static bodies (walls) have an inv_mass of
+// zero. `bounce` is the coefficient of restitution and `friction` is the
+// Coulomb friction coefficient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct RigidBody {
+    pub inv_mass: Number,
+    pub bounce: Number,
+    pub friction: Number,
+}
+impl RigidBody {
+    // A body that collisions never move
+    pub fn fixed(bounce: Number, friction: Number) -> RigidBody {
+        Self {
+            inv_mass: num!(0.),
+            bounce,
+            friction,
+        }
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.inv_mass == num!(0.)
+    }
+}
+impl Default for RigidBody {
+    fn default() -> Self {
+        Self {
+            inv_mass: num!(1.),
+            bounce: num!(1.),
+            friction: num!(0.),
+        }
+    }
+}
+
+// Hitbox, relative to the entity's `Transform`. Colliders with `ccd` set
+// are checked along their whole movement each frame (see `system_sweep`).
+//
+// Two colliders only meet if each is on one of the `layers` (a bitmask) in
+// the other's `mask`. Sensors (e.g., pickups or goal zones) never push or
+// get pushed, they only report overlaps (see `system_sensors`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct Collider {
+    pub rect: Rect<Number>,
+    pub ccd: bool,
+    pub layers: u32,
+    pub mask: u32,
+    pub sensor: bool,
+}
+impl Collider {
+    // On the first layer, meeting every other collider
+    pub fn new(rect: Rect<Number>) -> Collider {
+        Self {
+            rect,
+            ccd: false,
+            layers: 1,
+            mask: u32::MAX,
+            sensor: false,
+        }
+    }
+
+    // For bodies fast enough to pass through others between frames
+    pub fn with_ccd(self) -> Collider {
+        Self { ccd: true, ..self }
+    }
+
+    pub fn with_layers(self, layers: u32, mask: u32) -> Collider {
+        Self {
+            layers,
+            mask,
+            ..self
+        }
+    }
+
+    pub fn with_sensor(self) -> Collider {
+        Self {
+            sensor: true,
+            ..self
+        }
+    }
+
+    pub fn meets(&self, other: &Collider) -> bool {
+        self.layers & other.mask != 0 && other.layers & self.mask != 0
+    }
+
+    // Whether the two should push each other apart when they overlap
+    pub fn collides_with(&self, other: &Collider) -> bool {
+        !self.sensor && !other.sensor && self.meets(other)
+    }
+
+    // Hitbox in screen space
+    pub fn bounds(&self, transform: &Transform) -> Rect<Number> {
+        self.rect.translate(transform.position)
+    }
+
+    // Hitbox in screen space, rotated with the entity about its center
+    pub fn oriented_bounds(&self, transform: &Transform) -> OrientedRect<Number> {
+        OrientedRect::from_rect(self.bounds(transform), transform.angle)
+    }
+}
+
+// Marks an entity as moved by game code rather than `system_integrate`
+#[derive(Debug, Clone, Copy, Default, Component)]
+#[component(storage = "sparse")]
+pub struct Kinematic;
+
+// Accelerate, clamp to `MaxSpeed` (if the entity has one), then move every
+// entity that isn't `Kinematic`. Velocities are in pixels per frame.
+pub fn system_integrate(world: &mut World) {
+    for (mut transform, mut velocity, max_speed) in
+        world.query::<(&mut Transform, &mut Velocity, Option<&MaxSpeed>), _>(
+            &Without::<Kinematic>::new(),
+        )
+    {
+        let acceleration = velocity.acceleration;
+        velocity.velocity += acceleration;
+        if let Some(max_speed) = max_speed {
+            velocity.clamp(&max_speed);
+        }
+        transform.position += velocity.velocity;
+        transform.angle += velocity.rotation;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agb::fixnum::num;
+
+    use super::{system_integrate, Collider, Kinematic, MaxSpeed, Transform, Velocity};
+    use crate::ecs::World;
+    use crate::types::{Rect, Vector2D};
+
+    #[test_case]
+    fn test_integrate(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let ball = world.spawn((
+            Transform::at(Vector2D::new(num!(10.), num!(10.))),
+            Velocity {
+                velocity: Vector2D::new(num!(1.), num!(-0.5)),
+                acceleration: Vector2D::new(num!(0.5), num!(0.)),
+                rotation: num!(0.25),
+            },
+        ));
+        let fast = world.spawn((
+            Transform::default(),
+            Velocity {
+                acceleration: Vector2D::new(num!(1.), num!(-1.)),
+                ..Default::default()
+            },
+            MaxSpeed::symmetric(num!(1.5)),
+        ));
+        let paddle = world.spawn((
+            Transform::default(),
+            Velocity::new(Vector2D::new(num!(1.), num!(1.))),
+            Kinematic,
+        ));
+
+        system_integrate(&mut world);
+        system_integrate(&mut world);
+
+        let transform = *world.entry::<&Transform>(&ball);
+        assert_eq!(transform.position, Vector2D::new(num!(13.5), num!(9.)));
+        assert_eq!(transform.angle, num!(0.5));
+        assert_eq!(
+            world.entry::<&Velocity>(&fast).velocity,
+            Vector2D::new(num!(1.5), num!(-1.5))
+        );
+        assert_eq!(
+            world.entry::<&Transform>(&fast).position,
+            Vector2D::new(num!(2.5), num!(-2.5))
+        );
+        assert_eq!(*world.entry::<&Transform>(&paddle), Transform::default());
+    }
+
+    #[test_case]
+    fn test_collider_bounds(_gba: &mut agb::Gba) {
+        let collider = Collider::new(Rect::new(
+            Vector2D::new(num!(3.), num!(0.)),
+            Vector2D::new(num!(10.), num!(16.)),
+        ));
+        let bounds = collider.bounds(&Transform::at(Vector2D::new(num!(20.), num!(40.))));
+        assert_eq!(bounds.position, Vector2D::new(num!(23.), num!(40.)));
+        assert_eq!(bounds.size, collider.rect.size);
+
+        let mut transform = Transform::at(Vector2D::new(num!(20.), num!(40.)));
+        transform.angle = num!(0.25);
+        let oriented = collider.oriented_bounds(&transform);
+        assert_eq!(oriented.center, Vector2D::new(num!(28.), num!(48.)));
+        assert_eq!(oriented.half_size, Vector2D::new(num!(5.), num!(8.)));
+        assert_eq!(oriented.angle, num!(0.25));
+    }
+
+    #[test_case]
+    fn test_collider_layers(_gba: &mut agb::Gba) {
+        let rect = Rect::new(
+            Vector2D::new(num!(0.), num!(0.)),
+            Vector2D::new(num!(8.), num!(8.)),
+        );
+        let (player, enemy, bullet) = (0b0010, 0b0100, 0b1000);
+        let ship = Collider::new(rect).with_layers(player, enemy);
+        let alien = Collider::new(rect).with_layers(enemy, player | bullet);
+        let shot = Collider::new(rect).with_layers(bullet, enemy);
+        let wall = Collider::new(rect);
+
+        assert!(ship.collides_with(&alien) && alien.collides_with(&ship));
+        assert!(shot.collides_with(&alien));
+        assert!(!shot.collides_with(&ship));
+        // The wall meets everything, but the ship's mask leaves it out
+        assert!(wall.collides_with(&wall));
+        assert!(!wall.collides_with(&ship));
+
+        // Sensors meet colliders without colliding
+        let goal = Collider::new(rect).with_sensor();
+        assert!(goal.meets(&wall));
+        assert!(!goal.collides_with(&wall) && !wall.collides_with(&goal));
+    }
+}
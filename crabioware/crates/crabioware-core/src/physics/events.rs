@@ -0,0 +1,17 @@
+use crate::ecs::EntityId;
+use crate::types::{Number, Vector2D};
+
+// Sent when two entities collide. The normal points from `a` towards `b`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollisionEvent {
+    pub a: EntityId,
+    pub b: EntityId,
+    pub normal: Vector2D<Number>,
+}
+
+// Sent when a sensor overlaps another collider (which may be a sensor too)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SensorEvent {
+    pub sensor: EntityId,
+    pub other: EntityId,
+}
@@ -1,15 +1,8 @@
-use crate::types::{Rect, RectMath, Vector2D};
+use crate::types::{Circle, OrientedRect, Polygon, Rect, VecMath, Vector2D};
 use agb::fixnum::num;
 use agb::fixnum::FixedNum;
 use agb::fixnum::Number as AGBNumber;
-
-// FIXME: we want to support,
-//  1. Circle
-//  2. AABB
-//  3. Rect
-//  4. Arbitrary polygon
-//  5. Rays
-// in at least collision detection
+use alloc::vec::Vec;
 
 pub struct SeparationResult<N>
 where
@@ -20,21 +13,22 @@ where
     pub distance: N,
 }
 
-// This trait extends shapes with maths (RectMath) to define
-// intersection areas and collision normals.
-pub trait Intersects<N>: RectMath<N>
+// This trait extends shapes to define intersection areas and collision
+// normals against another shape (by default, the same kind of shape).
+// Separations point from `self` towards `other`.
+pub trait Intersects<N, Other = Self>
 where
     N: AGBNumber,
 {
     type Shape;
-    fn intersection(&self, other: &Self::Shape) -> Option<Self::Shape>;
-    fn separation(&self, other: &Self::Shape) -> Option<SeparationResult<N>>;
+    fn intersection(&self, other: &Other) -> Option<Self::Shape>;
+    fn separation(&self, other: &Other) -> Option<SeparationResult<N>>;
 }
 
 impl<const N: usize> Intersects<FixedNum<N>> for Rect<FixedNum<N>> {
     type Shape = Rect<FixedNum<N>>;
 
-    fn intersection(&self, other: &Self::Shape) -> Option<Self::Shape> {
+    fn intersection(&self, other: &Self) -> Option<Self::Shape> {
         if let Some(overlap) = self.overlapping_rect(*other) {
             Some(Self::Shape {
                 position: overlap.position,
@@ -45,7 +39,7 @@ impl<const N: usize> Intersects<FixedNum<N>> for Rect<FixedNum<N>> {
         }
     }
 
-    fn separation(&self, other: &Self::Shape) -> Option<SeparationResult<FixedNum<N>>> {
+    fn separation(&self, other: &Self) -> Option<SeparationResult<FixedNum<N>>> {
         if let Some(mut intersection) = self.intersection(other) {
             // Unless equal, only consider the minimum axis of separation for AABBs (rectangles)
             if intersection.size.x.abs() < intersection.size.y.abs() {
@@ -87,12 +81,398 @@ impl<const N: usize> Intersects<FixedNum<N>> for Rect<FixedNum<N>> {
     }
 }
 
+// When (as a fraction of their motion) and how one rectangle first touches
+// another as they move. The normal points from the first towards the second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeOfImpact<N>
+where
+    N: AGBNumber,
+{
+    pub time: N,
+    pub normal: Vector2D<N>,
+}
+
+// Swept AABB: find when `a` hits `b` as they move by `motion_a` and
+// `motion_b`, which collision checks after moving miss if either moves
+// further than it is thick. Rectangles that already overlap, or don't
+// overlap until after their motion, never impact.
+pub fn time_of_impact<const N: usize>(
+    a: &Rect<FixedNum<N>>,
+    motion_a: Vector2D<FixedNum<N>>,
+    b: &Rect<FixedNum<N>>,
+    motion_b: Vector2D<FixedNum<N>>,
+) -> Option<TimeOfImpact<FixedNum<N>>> {
+    // Sweep a's top left corner through b, grown by a's size, with b still
+    let motion = motion_a - motion_b;
+    let min = b.position - a.size;
+    let max = b.position + b.size;
+    let axes = [
+        (
+            a.position.x,
+            motion.x,
+            min.x,
+            max.x,
+            Vector2D::new(num!(1.), num!(0.)),
+        ),
+        (
+            a.position.y,
+            motion.y,
+            min.y,
+            max.y,
+            Vector2D::new(num!(0.), num!(1.)),
+        ),
+    ];
+
+    // The latest time the corner enters one of the axes' slabs (and the
+    // normal of that side), and the earliest time it leaves one
+    let mut first: Option<(FixedNum<N>, Vector2D<FixedNum<N>>)> = None;
+    let mut last = num!(1.);
+    for (origin, motion, min, max, normal) in axes {
+        if motion == num!(0.) {
+            // Never overlapping on this axis, or always
+            if origin <= min || origin >= max {
+                return None;
+            }
+            continue;
+        }
+
+        let enter = (min - origin) / motion;
+        let exit = (max - origin) / motion;
+        let (enter, exit, normal) = match enter < exit {
+            true => (enter, exit, normal),
+            false => (exit, enter, -normal),
+        };
+        if first.is_none_or(|(time, _)| enter > time) {
+            first = Some((enter, normal));
+        }
+        last = last.min(exit);
+    }
+
+    let (time, normal) = first?;
+    match time >= num!(0.) && time < last {
+        true => Some(TimeOfImpact { time, normal }),
+        false => None,
+    }
+}
+
+// Circles are pushed apart along the line between their centers, or from
+// the closest point on a rectangle, by how far they overlap. Touching isn't
+// overlapping.
+fn pushed_by<const N: usize>(
+    normal: Vector2D<FixedNum<N>>,
+    distance: FixedNum<N>,
+) -> SeparationResult<FixedNum<N>> {
+    SeparationResult {
+        separation: normal * distance,
+        normal,
+        distance,
+    }
+}
+
+// The contact of a circle with another shape is a circle halfway through
+// the overlap, with the penetration depth as its diameter
+fn contact<const N: usize>(
+    circle: &Circle<FixedNum<N>>,
+    separation: &SeparationResult<FixedNum<N>>,
+) -> Circle<FixedNum<N>> {
+    let radius = separation.distance * num!(0.5);
+    Circle::new(
+        circle.center + separation.normal * (circle.radius - radius),
+        radius,
+    )
+}
+
+impl<const N: usize> Intersects<FixedNum<N>> for Circle<FixedNum<N>> {
+    type Shape = Circle<FixedNum<N>>;
+
+    fn intersection(&self, other: &Self) -> Option<Self::Shape> {
+        self.separation(other)
+            .map(|separation| contact(self, &separation))
+    }
+
+    fn separation(&self, other: &Self) -> Option<SeparationResult<FixedNum<N>>> {
+        let offset = other.center - self.center;
+        let radii = self.radius + other.radius;
+        let distance_squared = offset.magnitude_squared();
+        if distance_squared >= radii * radii {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+        // Assume separation in x axis if they're on top of one another
+        let normal = match distance == num!(0.) {
+            true => Vector2D {
+                x: num!(1.),
+                y: num!(0.),
+            },
+            false => offset / distance,
+        };
+        Some(pushed_by(normal, radii - distance))
+    }
+}
+
+impl<const N: usize> Intersects<FixedNum<N>, Rect<FixedNum<N>>> for Circle<FixedNum<N>> {
+    type Shape = Circle<FixedNum<N>>;
+
+    fn intersection(&self, other: &Rect<FixedNum<N>>) -> Option<Self::Shape> {
+        self.separation(other)
+            .map(|separation| contact(self, &separation))
+    }
+
+    fn separation(&self, other: &Rect<FixedNum<N>>) -> Option<SeparationResult<FixedNum<N>>> {
+        let min = other.position;
+        let max = other.position + other.size;
+        let closest = Vector2D {
+            x: self.center.x.clamp(min.x, max.x),
+            y: self.center.y.clamp(min.y, max.y),
+        };
+
+        let offset = closest - self.center;
+        let distance_squared = offset.magnitude_squared();
+        if distance_squared >= self.radius * self.radius {
+            return None;
+        }
+        if distance_squared > num!(0.) {
+            let distance = distance_squared.sqrt();
+            return Some(pushed_by(offset / distance, self.radius - distance));
+        }
+
+        // The center is inside the rectangle, so push it out through the
+        // nearest edge (the normal still points from the circle to the rect)
+        let edges = [
+            (self.center.x - min.x, num!(1.), num!(0.)),
+            (max.x - self.center.x, num!(-1.), num!(0.)),
+            (self.center.y - min.y, num!(0.), num!(1.)),
+            (max.y - self.center.y, num!(0.), num!(-1.)),
+        ];
+        let (edge, x, y) = edges.into_iter().min_by_key(|(edge, _, _)| *edge).unwrap();
+        Some(pushed_by(Vector2D { x, y }, self.radius + edge))
+    }
+}
+
+impl<const N: usize> Intersects<FixedNum<N>, Circle<FixedNum<N>>> for Rect<FixedNum<N>> {
+    type Shape = Circle<FixedNum<N>>;
+
+    fn intersection(&self, other: &Circle<FixedNum<N>>) -> Option<Self::Shape> {
+        other.intersection(self)
+    }
+
+    fn separation(&self, other: &Circle<FixedNum<N>>) -> Option<SeparationResult<FixedNum<N>>> {
+        other.separation(self).map(|separation| SeparationResult {
+            separation: -separation.separation,
+            normal: -separation.normal,
+            distance: separation.distance,
+        })
+    }
+}
+
+// Convex shapes are tested with the Separating Axis Theorem: they don't
+// overlap if their projections onto one of their edge normals don't. If they
+// overlap on every axis, the axis needing the least push separates them.
+fn sat<const N: usize>(
+    a: &[Vector2D<FixedNum<N>>],
+    b: &[Vector2D<FixedNum<N>>],
+) -> Option<SeparationResult<FixedNum<N>>> {
+    let mut best: Option<SeparationResult<FixedNum<N>>> = None;
+    for axis in edge_normals(a).chain(edge_normals(b)) {
+        let (a_min, a_max) = project(a, axis);
+        let (b_min, b_max) = project(b, axis);
+        // How far to push b along the axis (forwards or backwards) to clear a
+        let (distance, normal) = match a_max - b_min < b_max - a_min {
+            true => (a_max - b_min, axis),
+            false => (b_max - a_min, -axis),
+        };
+        if distance <= num!(0.) {
+            return None;
+        }
+        if best.as_ref().is_none_or(|best| distance < best.distance) {
+            best = Some(pushed_by(normal, distance));
+        }
+    }
+    best
+}
+
+// Unit normals of each edge, skipping repeated vertices
+fn edge_normals<const N: usize>(
+    vertices: &[Vector2D<FixedNum<N>>],
+) -> impl Iterator<Item = Vector2D<FixedNum<N>>> + '_ {
+    let next = vertices.iter().cycle().skip(1);
+    vertices.iter().zip(next).filter_map(|(start, end)| {
+        let edge = *end - *start;
+        let length = edge.magnitude();
+        match length == num!(0.) {
+            true => None,
+            false => Some(Vector2D {
+                x: edge.y / length,
+                y: -edge.x / length,
+            }),
+        }
+    })
+}
+
+fn project<const N: usize>(
+    vertices: &[Vector2D<FixedNum<N>>],
+    axis: Vector2D<FixedNum<N>>,
+) -> (FixedNum<N>, FixedNum<N>) {
+    vertices
+        .iter()
+        .map(|vertex| vertex.dot(axis))
+        .fold(None, |bounds, projection| match bounds {
+            None => Some((projection, projection)),
+            Some((min, max)) => Some((
+                FixedNum::min(min, projection),
+                FixedNum::max(max, projection),
+            )),
+        })
+        .unwrap_or_default()
+}
+
+// The overlap of two convex shapes, by clipping the vertices of `subject`
+// against each edge of `clip` (Sutherland-Hodgman)
+fn clip<const N: usize>(
+    subject: &[Vector2D<FixedNum<N>>],
+    clip: &[Vector2D<FixedNum<N>>],
+) -> Polygon<FixedNum<N>> {
+    // Which side of the edges is inside depends on the winding
+    let next = clip.iter().cycle().skip(1);
+    let winding: FixedNum<N> = clip
+        .iter()
+        .zip(next.clone())
+        .map(|(start, end)| start.cross(*end))
+        .fold(num!(0.), |area, cross| area + cross);
+
+    let mut vertices: Vec<_> = subject.to_vec();
+    for (start, end) in clip.iter().zip(next) {
+        let edge = *end - *start;
+        let side = |point: Vector2D<FixedNum<N>>| match winding < num!(0.) {
+            true => -edge.cross(point - *start),
+            false => edge.cross(point - *start),
+        };
+
+        let input = core::mem::take(&mut vertices);
+        let previous = input.iter().cycle().skip(input.len().saturating_sub(1));
+        for (from, to) in previous.zip(input.iter()) {
+            let (side_from, side_to) = (side(*from), side(*to));
+            if (side_from >= num!(0.)) != (side_to >= num!(0.)) {
+                let t = side_from / (side_from - side_to);
+                vertices.push(*from + (*to - *from) * t);
+            }
+            if side_to >= num!(0.) {
+                vertices.push(*to);
+            }
+        }
+    }
+    Polygon::new(vertices)
+}
+
+fn rect_corners<const N: usize>(rect: &Rect<FixedNum<N>>) -> [Vector2D<FixedNum<N>>; 4] {
+    let (min, size) = (rect.position, rect.size);
+    [
+        min,
+        min + Vector2D {
+            x: size.x,
+            y: num!(0.),
+        },
+        min + size,
+        min + Vector2D {
+            x: num!(0.),
+            y: size.y,
+        },
+    ]
+}
+
+impl<const N: usize> Intersects<FixedNum<N>> for Polygon<FixedNum<N>> {
+    type Shape = Polygon<FixedNum<N>>;
+
+    fn intersection(&self, other: &Self) -> Option<Self::Shape> {
+        self.separation(other)
+            .map(|_| clip(&self.vertices, &other.vertices))
+    }
+
+    fn separation(&self, other: &Self) -> Option<SeparationResult<FixedNum<N>>> {
+        sat(&self.vertices, &other.vertices)
+    }
+}
+
+impl<const N: usize> Intersects<FixedNum<N>> for OrientedRect<FixedNum<N>> {
+    type Shape = Polygon<FixedNum<N>>;
+
+    fn intersection(&self, other: &Self) -> Option<Self::Shape> {
+        self.separation(other)
+            .map(|_| clip(&self.corners(), &other.corners()))
+    }
+
+    fn separation(&self, other: &Self) -> Option<SeparationResult<FixedNum<N>>> {
+        sat(&self.corners(), &other.corners())
+    }
+}
+
+impl<const N: usize> Intersects<FixedNum<N>, Polygon<FixedNum<N>>> for OrientedRect<FixedNum<N>> {
+    type Shape = Polygon<FixedNum<N>>;
+
+    fn intersection(&self, other: &Polygon<FixedNum<N>>) -> Option<Self::Shape> {
+        self.separation(other)
+            .map(|_| clip(&self.corners(), &other.vertices))
+    }
+
+    fn separation(&self, other: &Polygon<FixedNum<N>>) -> Option<SeparationResult<FixedNum<N>>> {
+        sat(&self.corners(), &other.vertices)
+    }
+}
+
+impl<const N: usize> Intersects<FixedNum<N>, OrientedRect<FixedNum<N>>> for Polygon<FixedNum<N>> {
+    type Shape = Polygon<FixedNum<N>>;
+
+    fn intersection(&self, other: &OrientedRect<FixedNum<N>>) -> Option<Self::Shape> {
+        self.separation(other)
+            .map(|_| clip(&self.vertices, &other.corners()))
+    }
+
+    fn separation(
+        &self,
+        other: &OrientedRect<FixedNum<N>>,
+    ) -> Option<SeparationResult<FixedNum<N>>> {
+        sat(&self.vertices, &other.corners())
+    }
+}
+
+impl<const N: usize> Intersects<FixedNum<N>, Rect<FixedNum<N>>> for OrientedRect<FixedNum<N>> {
+    type Shape = Polygon<FixedNum<N>>;
+
+    fn intersection(&self, other: &Rect<FixedNum<N>>) -> Option<Self::Shape> {
+        self.separation(other)
+            .map(|_| clip(&self.corners(), &rect_corners(other)))
+    }
+
+    fn separation(&self, other: &Rect<FixedNum<N>>) -> Option<SeparationResult<FixedNum<N>>> {
+        sat(&self.corners(), &rect_corners(other))
+    }
+}
+
+impl<const N: usize> Intersects<FixedNum<N>, OrientedRect<FixedNum<N>>> for Rect<FixedNum<N>> {
+    type Shape = Polygon<FixedNum<N>>;
+
+    fn intersection(&self, other: &OrientedRect<FixedNum<N>>) -> Option<Self::Shape> {
+        self.separation(other)
+            .map(|_| clip(&rect_corners(self), &other.corners()))
+    }
+
+    fn separation(
+        &self,
+        other: &OrientedRect<FixedNum<N>>,
+    ) -> Option<SeparationResult<FixedNum<N>>> {
+        sat(&rect_corners(self), &other.corners())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::types::{Number, Rect};
+    use alloc::vec;
+
+    use crate::types::{Circle, Number, OrientedRect, Polygon, Rect, RectMath};
     use agb::fixnum::{num, Vector2D};
 
-    use super::Intersects;
+    use super::{time_of_impact, Intersects};
 
     #[test_case]
     fn test_rect_non_intersects(_gba: &mut agb::Gba) {
@@ -128,8 +508,192 @@ mod tests {
         let test_intersects = rect_a.intersection(&rect_b).unwrap();
         assert_eq!(intersection, test_intersects);
     }
-}
 
-// TODO: for a rotated rectangle, we can use Separating Axis Theorem
-// https://stackoverflow.com/questions/10962379/how-to-check-intersection-between-2-rotated-rectangles
-// (not SAT) https://stackoverflow.com/questions/62028169/how-to-detect-when-rotated-rectangles-are-colliding-each-other
+    #[test_case]
+    fn test_circle_intersects(_gba: &mut agb::Gba) {
+        let circle_a = Circle::<Number>::new(Vector2D::new(num!(0.), num!(0.)), num!(2.));
+        let circle_b = Circle::new(Vector2D::new(num!(3.), num!(0.)), num!(2.));
+        let circle_c = Circle::new(Vector2D::new(num!(3.), num!(3.)), num!(2.));
+
+        let separation = circle_a.separation(&circle_b).unwrap();
+        assert_eq!(separation.normal, Vector2D::new(num!(1.), num!(0.)));
+        assert_eq!(separation.distance, num!(1.));
+        assert_eq!(separation.separation, Vector2D::new(num!(1.), num!(0.)));
+        assert_eq!(
+            circle_a.intersection(&circle_b),
+            Some(Circle::new(Vector2D::new(num!(1.5), num!(0.)), num!(0.5)))
+        );
+
+        let separation = circle_b.separation(&circle_a).unwrap();
+        assert_eq!(separation.normal, Vector2D::new(num!(-1.), num!(0.)));
+
+        // Diagonally, they're 4.24 apart
+        assert!(circle_a.separation(&circle_c).is_none());
+        assert!(circle_a.intersection(&circle_c).is_none());
+    }
+
+    #[test_case]
+    fn test_circle_rect_intersects(_gba: &mut agb::Gba) {
+        let rect = Rect::<Number>::new(
+            Vector2D::new(num!(4.), num!(0.)),
+            Vector2D::new(num!(4.), num!(4.)),
+        );
+
+        // Beside the left edge
+        let circle = Circle::new(Vector2D::new(num!(3.), num!(2.)), num!(2.));
+        let separation = circle.separation(&rect).unwrap();
+        assert_eq!(separation.normal, Vector2D::new(num!(1.), num!(0.)));
+        assert_eq!(separation.distance, num!(1.));
+        let separation = rect.separation(&circle).unwrap();
+        assert_eq!(separation.normal, Vector2D::new(num!(-1.), num!(0.)));
+        assert_eq!(separation.separation, Vector2D::new(num!(-1.), num!(0.)));
+
+        // Off the bottom right corner
+        let circle = Circle::new(Vector2D::new(num!(9.), num!(5.)), num!(2.));
+        let separation = circle.separation(&rect).unwrap();
+        assert!(separation.normal.x < num!(0.) && separation.normal.y < num!(0.));
+        assert!(separation.distance > num!(0.) && separation.distance < num!(1.));
+
+        // Touching the top edge isn't overlapping
+        let circle = Circle::new(Vector2D::new(num!(6.), num!(-2.)), num!(2.));
+        assert!(circle.separation(&rect).is_none());
+        assert!(rect.intersection(&circle).is_none());
+
+        // Center inside, nearest the bottom edge
+        let circle = Circle::new(Vector2D::new(num!(6.), num!(3.)), num!(1.));
+        let separation = circle.separation(&rect).unwrap();
+        assert_eq!(separation.normal, Vector2D::new(num!(0.), num!(-1.)));
+        assert_eq!(separation.distance, num!(2.));
+    }
+
+    fn near(a: Number, b: Number) -> bool {
+        (a - b).abs() < num!(0.01)
+    }
+
+    #[test_case]
+    fn test_oriented_rect_intersects(_gba: &mut agb::Gba) {
+        // Unrotated, they separate like rectangles
+        let obb_a = OrientedRect::<Number>::new(
+            Vector2D::new(num!(2.), num!(2.)),
+            Vector2D::new(num!(2.), num!(2.)),
+            num!(0.),
+        );
+        let obb_b = OrientedRect::new(
+            Vector2D::new(num!(5.), num!(3.)),
+            Vector2D::new(num!(2.5), num!(3.)),
+            num!(0.),
+        );
+        let separation = obb_a.separation(&obb_b).unwrap();
+        assert_eq!(separation.normal, Vector2D::new(num!(1.), num!(0.)));
+        assert_eq!(separation.distance, num!(1.5));
+
+        // A diamond reaches 2.83 to the right of its center
+        let diamond = OrientedRect::new(
+            Vector2D::new(num!(0.), num!(0.)),
+            Vector2D::new(num!(2.), num!(2.)),
+            num!(0.125),
+        );
+        let rect = Rect::<Number>::new(
+            Vector2D::new(num!(2.5), num!(-1.)),
+            Vector2D::new(num!(2.), num!(2.)),
+        );
+        let separation = diamond.separation(&rect).unwrap();
+        assert!(near(separation.normal.x, num!(1.)) && near(separation.normal.y, num!(0.)));
+        assert!(near(separation.distance, num!(0.33)));
+        let separation = rect.separation(&diamond).unwrap();
+        assert!(near(separation.normal.x, num!(-1.)));
+        assert!(rect.intersection(&diamond).is_some());
+
+        // Their bounding boxes overlap, but the rect is past the diamond's edge
+        let rect = Rect::<Number>::new(
+            Vector2D::new(num!(1.6), num!(1.6)),
+            Vector2D::new(num!(2.), num!(2.)),
+        );
+        assert!(diamond.bounding_rect().intersection(&rect).is_some());
+        assert!(diamond.separation(&rect).is_none());
+        assert!(diamond.intersection(&rect).is_none());
+    }
+
+    #[test_case]
+    fn test_polygon_intersects(_gba: &mut agb::Gba) {
+        let triangle = Polygon::<Number>::new(vec![
+            Vector2D::new(num!(0.), num!(0.)),
+            Vector2D::new(num!(4.), num!(0.)),
+            Vector2D::new(num!(0.), num!(4.)),
+        ]);
+        let square = Polygon::<Number>::new(vec![
+            Vector2D::new(num!(1.), num!(1.)),
+            Vector2D::new(num!(1.), num!(3.)),
+            Vector2D::new(num!(3.), num!(3.)),
+            Vector2D::new(num!(3.), num!(1.)),
+        ]);
+
+        // The square pokes out past the hypotenuse
+        let separation = triangle.separation(&square).unwrap();
+        assert!(near(separation.normal.x, num!(0.707)) && near(separation.normal.y, num!(0.707)));
+        assert!(near(separation.distance, num!(1.414)));
+
+        let overlap = triangle.intersection(&square).unwrap();
+        assert_eq!(overlap.vertices.len(), 3);
+        for corner in [
+            Vector2D::new(num!(1.), num!(1.)),
+            Vector2D::new(num!(3.), num!(1.)),
+            Vector2D::new(num!(1.), num!(3.)),
+        ] {
+            assert!(overlap
+                .vertices
+                .iter()
+                .any(|vertex| near(vertex.x, corner.x) && near(vertex.y, corner.y)));
+        }
+
+        let square = square.translate(Vector2D::new(num!(2.), num!(2.)));
+        assert!(triangle.separation(&square).is_none());
+        assert!(square.intersection(&triangle).is_none());
+    }
+
+    #[test_case]
+    fn test_time_of_impact(_gba: &mut agb::Gba) {
+        let still = Vector2D::new(num!(0.), num!(0.));
+        let rect = Rect::<Number>::new(
+            Vector2D::new(num!(0.), num!(0.)),
+            Vector2D::new(num!(4.), num!(4.)),
+        );
+        let wall = Rect::<Number>::new(
+            Vector2D::new(num!(10.), num!(-2.)),
+            Vector2D::new(num!(2.), num!(8.)),
+        );
+
+        // Passes straight through the wall in one step, so overlap checks miss it
+        let impact =
+            time_of_impact(&rect, Vector2D::new(num!(20.), num!(0.)), &wall, still).unwrap();
+        assert_eq!(impact.time, num!(0.3));
+        assert_eq!(impact.normal, Vector2D::new(num!(1.), num!(0.)));
+        assert!(rect
+            .translate(Vector2D::new(num!(20.), num!(0.)))
+            .intersection(&wall)
+            .is_none());
+
+        // Only the relative motion matters
+        let impact =
+            time_of_impact(&wall, Vector2D::new(num!(-10.), num!(0.)), &rect, still).unwrap();
+        assert_eq!(impact.time, num!(0.6));
+        assert_eq!(impact.normal, Vector2D::new(num!(-1.), num!(0.)));
+        let impact = time_of_impact(
+            &rect,
+            Vector2D::new(num!(4.), num!(0.)),
+            &wall,
+            Vector2D::new(num!(-4.), num!(0.)),
+        )
+        .unwrap();
+        assert_eq!(impact.time, num!(0.75));
+
+        // Too short, moving away, passing by, or overlapping already
+        assert!(time_of_impact(&rect, Vector2D::new(num!(5.), num!(0.)), &wall, still).is_none());
+        assert!(time_of_impact(&rect, Vector2D::new(num!(-20.), num!(0.)), &wall, still).is_none());
+        assert!(time_of_impact(&rect, Vector2D::new(num!(20.), num!(20.)), &wall, still).is_none());
+        let inside = rect.translate(Vector2D::new(num!(8.), num!(0.)));
+        assert!(
+            time_of_impact(&inside, Vector2D::new(num!(20.), num!(0.)), &wall, still).is_none()
+        );
+    }
+}
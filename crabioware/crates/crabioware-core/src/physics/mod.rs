@@ -1,3 +1,17 @@
+pub mod body;
+pub mod events;
 pub mod intersect;
+pub mod raycast;
+pub mod resolve;
+pub mod sensors;
+pub mod spatial;
+pub mod tiles;
 
-pub use intersect::{Intersects, SeparationResult};
+pub use body::{system_integrate, Collider, Kinematic, MaxSpeed, RigidBody, Transform, Velocity};
+pub use events::{CollisionEvent, SensorEvent};
+pub use intersect::{time_of_impact, Intersects, SeparationResult, TimeOfImpact};
+pub use raycast::{raycast, raycast_tiles, RayHit, Raycast};
+pub use resolve::{resolve, system_resolve, system_sweep, ContactSlop};
+pub use sensors::system_sensors;
+pub use spatial::{system_spatial_hash, SpatialHash};
+pub use tiles::{Blocked, TileCollider, TileGrid};
@@ -0,0 +1,403 @@
+// Raycasts
+//
+// A ray is cast from a point in a direction, up to a maximum distance, and
+// reports the first thing it hits: where, the surface normal there, and how
+// far along the ray that was. Rays starting inside a shape hit it straight
+// away, at distance zero with the normal facing back along the ray.
+//
+// `raycast` checks the `Collider` of every entity matching a filter (e.g.,
+// for hitscan weapons, or to predict where a ball is going) and
+// `raycast_tiles` walks through a `TileGrid` one tile at a time.
+use core::cmp::Ordering;
+
+use agb::fixnum::{num, FixedNum, Number as AGBNumber};
+
+use super::body::{Collider, Transform};
+use super::tiles::TileGrid;
+use crate::ecs::{EntityId, EntityMapFilter, World};
+use crate::types::{Circle, Number, Ray, Rect, VecMath, Vector2D};
+
+// What a ray hit (e.g., an entity or a tile), and where
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RayHit<N, T = ()>
+where
+    N: AGBNumber,
+{
+    pub target: T,
+    pub point: Vector2D<N>,
+    pub normal: Vector2D<N>,
+    pub distance: N,
+}
+impl<N> RayHit<N>
+where
+    N: AGBNumber,
+{
+    pub fn with_target<T>(self, target: T) -> RayHit<N, T> {
+        RayHit {
+            target,
+            point: self.point,
+            normal: self.normal,
+            distance: self.distance,
+        }
+    }
+}
+
+// Shapes that rays can hit
+pub trait Raycast<N>
+where
+    N: AGBNumber,
+{
+    fn raycast(&self, ray: &Ray<N>, max_distance: N) -> Option<RayHit<N>>;
+}
+
+fn hit_inside<const N: usize>(ray: &Ray<FixedNum<N>>) -> RayHit<FixedNum<N>> {
+    RayHit {
+        target: (),
+        point: ray.origin,
+        normal: -ray.direction,
+        distance: num!(0.),
+    }
+}
+
+impl<const N: usize> Raycast<FixedNum<N>> for Rect<FixedNum<N>> {
+    fn raycast(
+        &self,
+        ray: &Ray<FixedNum<N>>,
+        max_distance: FixedNum<N>,
+    ) -> Option<RayHit<FixedNum<N>>> {
+        let min = self.position;
+        let max = self.position + self.size;
+        let axes = [
+            (
+                ray.origin.x,
+                ray.direction.x,
+                min.x,
+                max.x,
+                Vector2D::new(num!(-1.), num!(0.)),
+            ),
+            (
+                ray.origin.y,
+                ray.direction.y,
+                min.y,
+                max.y,
+                Vector2D::new(num!(0.), num!(-1.)),
+            ),
+        ];
+
+        // Distances along the ray where it's between both pairs of edges
+        // (the slabs), and the normal of the last edge it crossed to get there
+        let (mut near, mut far) = (num!(0.), max_distance);
+        let mut normal = None;
+        for (origin, direction, min, max, mut surface) in axes {
+            if direction == num!(0.) {
+                // Parallel to the slab, so it's always or never inside it
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+
+            let mut enter = (min - origin) / direction;
+            let mut exit = (max - origin) / direction;
+            if enter > exit {
+                (enter, exit) = (exit, enter);
+                surface = -surface;
+            }
+            if enter > near {
+                near = enter;
+                normal = Some(surface);
+            }
+            far = far.min(exit);
+            if near > far {
+                return None;
+            }
+        }
+
+        Some(match normal {
+            Some(normal) => RayHit {
+                target: (),
+                point: ray.at(near),
+                normal,
+                distance: near,
+            },
+            None => hit_inside(ray),
+        })
+    }
+}
+
+impl<const N: usize> Raycast<FixedNum<N>> for Circle<FixedNum<N>> {
+    fn raycast(
+        &self,
+        ray: &Ray<FixedNum<N>>,
+        max_distance: FixedNum<N>,
+    ) -> Option<RayHit<FixedNum<N>>> {
+        let offset = ray.origin - self.center;
+        let towards = offset.dot(ray.direction);
+        let outside = offset.magnitude_squared() - self.radius * self.radius;
+        if outside < num!(0.) {
+            return Some(hit_inside(ray));
+        }
+        if towards > num!(0.) {
+            // Pointing away from the circle
+            return None;
+        }
+
+        let discriminant = towards * towards - outside;
+        if discriminant < num!(0.) {
+            return None;
+        }
+        let distance = -towards - discriminant.sqrt();
+        if distance > max_distance {
+            return None;
+        }
+        let point = ray.at(distance);
+        Some(RayHit {
+            target: (),
+            point,
+            normal: (point - self.center) / self.radius,
+            distance,
+        })
+    }
+}
+
+// The first collider of an entity matching `filter` that a ray from `origin`
+// hits, within `max_distance`. `direction` needn't be normalised, but
+// mustn't be zero.
+pub fn raycast<F: EntityMapFilter>(
+    world: &World,
+    filter: &F,
+    origin: Vector2D<Number>,
+    direction: Vector2D<Number>,
+    max_distance: Number,
+) -> Option<RayHit<Number, EntityId>> {
+    let ray = Ray::new(origin, direction);
+    world
+        .query::<(EntityId, &Transform, &Collider), _>(filter)
+        .filter_map(|(entity, transform, collider)| {
+            collider
+                .bounds(&transform)
+                .raycast(&ray, max_distance)
+                .map(|hit| hit.with_target(entity))
+        })
+        .min_by_key(|hit| hit.distance)
+}
+
+// Distances along a ray to its next tile edge on one axis, and between
+// edges, with the direction it steps through tiles
+#[derive(Clone, Copy)]
+struct Crossings {
+    next: Number,
+    delta: Number,
+    step: i32,
+}
+impl Crossings {
+    fn new(origin: Number, direction: Number, tile: i32, size: i32) -> Option<Crossings> {
+        let (edge, step) = match direction.cmp(&num!(0.)) {
+            Ordering::Equal => return None,
+            Ordering::Greater => (tile + 1, 1),
+            Ordering::Less => (tile, -1),
+        };
+        Some(Crossings {
+            next: (Number::new(edge * size) - origin) / direction,
+            delta: (Number::new(size) / direction).abs(),
+            step,
+        })
+    }
+}
+
+// The first solid tile that a ray from `origin` enters, within
+// `max_distance`. `direction` needn't be normalised, but mustn't be zero.
+pub fn raycast_tiles<G: TileGrid>(
+    grid: &G,
+    origin: Vector2D<Number>,
+    direction: Vector2D<Number>,
+    max_distance: Number,
+) -> Option<RayHit<Number, Vector2D<i32>>> {
+    let ray = Ray::new(origin, direction);
+    let size = grid.tile_size();
+    let mut tile = grid.tile_at(origin);
+    if grid.is_solid(tile) {
+        return Some(hit_inside(&ray).with_target(tile));
+    }
+
+    let mut x = Crossings::new(origin.x, ray.direction.x, tile.x, size);
+    let mut y = Crossings::new(origin.y, ray.direction.y, tile.y, size);
+    loop {
+        // Step into whichever neighbouring tile the ray reaches first
+        let step_x = match (x, y) {
+            (Some(x), Some(y)) => x.next <= y.next,
+            (x, _) => x.is_some(),
+        };
+        let (crossings, normal) = match step_x {
+            true => {
+                let crossings = x.as_mut()?;
+                tile.x += crossings.step;
+                let normal = Vector2D::new(Number::new(-crossings.step), num!(0.));
+                (crossings, normal)
+            }
+            false => {
+                let crossings = y.as_mut()?;
+                tile.y += crossings.step;
+                let normal = Vector2D::new(num!(0.), Number::new(-crossings.step));
+                (crossings, normal)
+            }
+        };
+        let distance = crossings.next;
+        crossings.next += crossings.delta;
+
+        if distance > max_distance {
+            return None;
+        }
+        if grid.is_solid(tile) {
+            return Some(RayHit {
+                target: tile,
+                point: ray.at(distance),
+                normal,
+                distance,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agb::fixnum::num;
+
+    use super::{raycast, raycast_tiles, Raycast};
+    use crate::ecs::{With, World};
+    use crate::physics::{Collider, Kinematic, TileGrid, Transform};
+    use crate::types::{Circle, Number, Ray, Rect, Vector2D};
+
+    #[test_case]
+    fn test_raycast_shapes(_gba: &mut agb::Gba) {
+        let rect = Rect::<Number>::new(
+            Vector2D::new(num!(10.), num!(0.)),
+            Vector2D::new(num!(4.), num!(8.)),
+        );
+        let ray = Ray::new(
+            Vector2D::new(num!(0.), num!(2.)),
+            Vector2D::new(num!(2.), num!(0.)),
+        );
+        let hit = rect.raycast(&ray, num!(100.)).unwrap();
+        assert_eq!(hit.point, Vector2D::new(num!(10.), num!(2.)));
+        assert_eq!(hit.normal, Vector2D::new(num!(-1.), num!(0.)));
+        assert_eq!(hit.distance, num!(10.));
+        assert!(rect.raycast(&ray, num!(9.)).is_none());
+
+        // Starting inside, or pointing away
+        let inside = Ray::new(
+            Vector2D::new(num!(12.), num!(2.)),
+            Vector2D::new(num!(0.), num!(1.)),
+        );
+        let hit = rect.raycast(&inside, num!(100.)).unwrap();
+        assert_eq!(hit.distance, num!(0.));
+        assert_eq!(hit.normal, Vector2D::new(num!(0.), num!(-1.)));
+        let away = Ray::new(
+            Vector2D::new(num!(0.), num!(2.)),
+            Vector2D::new(num!(-1.), num!(0.)),
+        );
+        assert!(rect.raycast(&away, num!(100.)).is_none());
+
+        let circle = Circle::<Number>::new(Vector2D::new(num!(0.), num!(10.)), num!(2.));
+        let ray = Ray::new(
+            Vector2D::new(num!(0.), num!(0.)),
+            Vector2D::new(num!(0.), num!(1.)),
+        );
+        let hit = circle.raycast(&ray, num!(100.)).unwrap();
+        assert_eq!(hit.point, Vector2D::new(num!(0.), num!(8.)));
+        assert_eq!(hit.normal, Vector2D::new(num!(0.), num!(-1.)));
+        assert_eq!(hit.distance, num!(8.));
+        let miss = Ray::new(
+            Vector2D::new(num!(3.), num!(0.)),
+            Vector2D::new(num!(0.), num!(1.)),
+        );
+        assert!(circle.raycast(&miss, num!(100.)).is_none());
+    }
+
+    #[test_case]
+    fn test_raycast_world(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let size = Vector2D::new(num!(4.), num!(4.));
+        let near = world.spawn((
+            Transform::at(Vector2D::new(num!(20.), num!(0.))),
+            Collider::new(Rect::new(Vector2D::new(num!(0.), num!(0.)), size)),
+        ));
+        let far = world.spawn((
+            Transform::at(Vector2D::new(num!(40.), num!(0.))),
+            Collider::new(Rect::new(Vector2D::new(num!(0.), num!(0.)), size)),
+            Kinematic,
+        ));
+
+        let (origin, right) = (
+            Vector2D::new(num!(0.), num!(2.)),
+            Vector2D::new(num!(1.), num!(0.)),
+        );
+        let hit = raycast(&world, &(), origin, right, num!(100.)).unwrap();
+        assert_eq!(hit.target, near);
+        assert_eq!(hit.distance, num!(20.));
+
+        let hit = raycast(&world, &With::<Kinematic>::new(), origin, right, num!(100.)).unwrap();
+        assert_eq!(hit.target, far);
+        assert_eq!(hit.point, Vector2D::new(num!(40.), num!(2.)));
+
+        let left = Vector2D::new(num!(-1.), num!(0.));
+        assert!(raycast(&world, &(), origin, left, num!(100.)).is_none());
+    }
+
+    // 4x4 tiles of 8 pixels, with walls at (2, 1) and (1, 3)
+    struct Walls;
+    impl TileGrid for Walls {
+        fn tile_size(&self) -> i32 {
+            8
+        }
+
+        fn is_solid(&self, tile: Vector2D<i32>) -> bool {
+            matches!((tile.x, tile.y), (2, 1) | (1, 3))
+        }
+    }
+
+    #[test_case]
+    fn test_raycast_tiles(_gba: &mut agb::Gba) {
+        let hit = raycast_tiles(
+            &Walls,
+            Vector2D::new(num!(4.), num!(12.)),
+            Vector2D::new(num!(1.), num!(0.)),
+            num!(100.),
+        )
+        .unwrap();
+        assert_eq!(hit.target, Vector2D::new(2, 1));
+        assert_eq!(hit.point, Vector2D::new(num!(16.), num!(12.)));
+        assert_eq!(hit.normal, Vector2D::new(num!(-1.), num!(0.)));
+        assert_eq!(hit.distance, num!(12.));
+
+        // Straight down past the wall into the one below
+        let hit = raycast_tiles(
+            &Walls,
+            Vector2D::new(num!(12.), num!(2.)),
+            Vector2D::new(num!(0.), num!(3.)),
+            num!(100.),
+        )
+        .unwrap();
+        assert_eq!(hit.target, Vector2D::new(1, 3));
+        assert_eq!(hit.normal, Vector2D::new(num!(0.), num!(-1.)));
+        assert_eq!(hit.distance, num!(22.));
+
+        // Diagonally up and left, through the gaps
+        let up_left = raycast_tiles(
+            &Walls,
+            Vector2D::new(num!(20.), num!(20.)),
+            Vector2D::new(num!(-1.), num!(-1.)),
+            num!(100.),
+        );
+        assert!(up_left.is_none());
+
+        // Out of reach
+        let short = raycast_tiles(
+            &Walls,
+            Vector2D::new(num!(4.), num!(12.)),
+            Vector2D::new(num!(1.), num!(0.)),
+            num!(11.),
+        );
+        assert!(short.is_none());
+    }
+}
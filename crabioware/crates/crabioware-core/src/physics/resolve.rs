@@ -0,0 +1,558 @@
+// Impulse based collision response
+//
+// `resolve` pushes two overlapping bodies apart along the collision normal
+// (in proportion to their inverse masses, so static bodies never move), then
+// applies a restitution impulse and a Coulomb friction impulse if they're
+// moving towards each other. Overlaps smaller than the slop are left alone,
+// so resting bodies don't jitter from correcting tiny rounding errors.
+//
+// `system_resolve` does this for every pair of entities with colliders,
+// sending a `CollisionEvent` for each (if the world has that event channel).
+// With a `SpatialHash` resource, only pairs sharing one of its cells are
+// checked. `system_sweep` does the same for fast bodies before they move,
+// so they bounce off what they'd hit rather than passing through it.
+use agb::fixnum::num;
+use alloc::vec::Vec;
+
+use super::body::{Collider, Kinematic, MaxSpeed, RigidBody, Transform, Velocity};
+use super::events::CollisionEvent;
+use super::intersect::{time_of_impact, Intersects, SeparationResult, TimeOfImpact};
+use super::spatial::SpatialHash;
+use crate::ecs::{EntityId, Events, Without, World};
+use crate::types::{Number, VecMath, Vector2D};
+
+// Penetration depth (in pixels) to allow before pushing bodies apart. Store
+// it as a resource to change it for `system_resolve`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContactSlop(pub Number);
+impl Default for ContactSlop {
+    fn default() -> Self {
+        Self(num!(0.05))
+    }
+}
+
+// Resolve a collision between `a` and `b`, where the separation points from
+// `a` towards `b`. Returns true if an impulse was applied, i.e., the bodies
+// were moving towards each other.
+pub fn resolve(
+    separation: &SeparationResult<Number>,
+    slop: Number,
+    (transform_a, velocity_a, body_a): (&mut Transform, &mut Velocity, &RigidBody),
+    (transform_b, velocity_b, body_b): (&mut Transform, &mut Velocity, &RigidBody),
+) -> bool {
+    let inv_masses = body_a.inv_mass + body_b.inv_mass;
+    if inv_masses == num!(0.) {
+        // Two static bodies can't push each other
+        return false;
+    }
+
+    // Unstick, leaving up to the slop overlapping
+    if separation.distance > slop {
+        let correction = separation.separation - separation.normal * slop;
+        transform_a.position -= correction * body_a.inv_mass / inv_masses;
+        transform_b.position += correction * body_b.inv_mass / inv_masses;
+    }
+
+    // Don't update if already moving away
+    let normal = separation.normal;
+    let relative_velocity = velocity_a.velocity - velocity_b.velocity;
+    let relative_velocity_norm = relative_velocity.dot(normal);
+    if relative_velocity_norm <= num!(0.) {
+        return false;
+    }
+
+    // Restitution
+    let elasticity = body_a.bounce.min(body_b.bounce);
+    let impulse = -(num!(1.) + elasticity) * relative_velocity_norm / inv_masses;
+    velocity_a.velocity += normal * impulse * body_a.inv_mass;
+    velocity_b.velocity -= normal * impulse * body_b.inv_mass;
+
+    // Friction opposes the remaining sliding velocity, but can't exceed the
+    // restitution impulse scaled by the friction coefficient
+    let friction = (body_a.friction * body_b.friction).sqrt();
+    let relative_velocity = velocity_a.velocity - velocity_b.velocity;
+    let tangent = relative_velocity - normal * relative_velocity.dot(normal);
+    let sliding = tangent.magnitude();
+    if friction > num!(0.) && sliding > num!(0.) {
+        let tangent = tangent / sliding;
+        let max_impulse = friction * impulse.abs();
+        let tangent_impulse = (-sliding / inv_masses).clamp(-max_impulse, max_impulse);
+        velocity_a.velocity += tangent * tangent_impulse * body_a.inv_mass;
+        velocity_b.velocity -= tangent * tangent_impulse * body_b.inv_mass;
+    }
+    true
+}
+
+// Resolve collisions between every pair of entities with a `Transform`,
+// `Velocity`, `RigidBody` and `Collider`, clamping the result to `MaxSpeed`.
+// If the world has a `SpatialHash`, only its candidate pairs are checked.
+// Sensors, and colliders whose layers the other's mask leaves out, are
+// skipped.
+pub fn system_resolve(world: &mut World) {
+    type Body<'a> = (
+        EntityId,
+        &'a mut Transform,
+        &'a mut Velocity,
+        &'a RigidBody,
+        &'a Collider,
+        Option<&'a MaxSpeed>,
+    );
+
+    let slop = world
+        .resource::<ContactSlop>()
+        .map(|slop| *slop)
+        .unwrap_or_default()
+        .0;
+    let send_events = world.has_resource::<Events<CollisionEvent>>();
+    let pairs = world.resource::<SpatialHash>().map(|hash| hash.pairs());
+    let candidates = pairs
+        .as_deref()
+        .map(|pairs| world.candidates::<Body, Body>(pairs));
+    let everything = candidates.is_none().then(|| world.combinations::<Body>());
+    for (
+        (entity_a, mut transform_a, mut velocity_a, body_a, collider_a, max_speed_a),
+        (entity_b, mut transform_b, mut velocity_b, body_b, collider_b, max_speed_b),
+    ) in candidates
+        .into_iter()
+        .flatten()
+        .chain(everything.into_iter().flatten())
+    {
+        if !collider_a.collides_with(&collider_b) {
+            continue;
+        }
+        let bounds_a = collider_a.bounds(&transform_a);
+        let bounds_b = collider_b.bounds(&transform_b);
+        let Some(separation) = bounds_a.separation(&bounds_b) else {
+            continue;
+        };
+        if send_events {
+            world.send_event(CollisionEvent {
+                a: entity_a,
+                b: entity_b,
+                normal: separation.normal,
+            });
+        }
+
+        let impulse = resolve(
+            &separation,
+            slop,
+            (&mut transform_a, &mut velocity_a, &body_a),
+            (&mut transform_b, &mut velocity_b, &body_b),
+        );
+        if impulse {
+            if let Some(max_speed) = max_speed_a {
+                velocity_a.clamp(&max_speed);
+            }
+            if let Some(max_speed) = max_speed_b {
+                velocity_b.clamp(&max_speed);
+            }
+        }
+    }
+}
+
+// Continuous collision detection for colliders with `ccd` set. Run before
+// `system_integrate`: bodies that would hit each other during the frame are
+// bounced at the point of impact, and placed so that integrating them covers
+// the rest of the frame from there (rather than moving through what they
+// hit). `Kinematic` bodies are moved by game code instead, so they're swept
+// where they are. Only the first impact of each body is handled, and the
+// sweep ignores this frame's acceleration.
+pub fn system_sweep(world: &mut World) {
+    type Body<'a> = (
+        EntityId,
+        &'a Transform,
+        &'a Velocity,
+        &'a RigidBody,
+        &'a Collider,
+        Option<&'a Kinematic>,
+    );
+    type Resolvable<'a> = (
+        &'a mut Transform,
+        &'a mut Velocity,
+        &'a RigidBody,
+        Option<&'a MaxSpeed>,
+    );
+
+    // Find every impact before moving anything. Pairs come grouped by their
+    // first entity, so only the last one pushed needs comparing against.
+    let mut impacts = Vec::<(EntityId, EntityId, TimeOfImpact<Number>)>::new();
+    for (
+        (entity_a, transform_a, velocity_a, _, collider_a, _),
+        (entity_b, transform_b, velocity_b, _, collider_b, kinematic_b),
+    ) in world.query_pairs::<Body, Body, _, _>(&Without::<Kinematic>::new(), &())
+    {
+        if !collider_a.ccd || !collider_a.collides_with(&collider_b) {
+            continue;
+        }
+        let motion_b = match kinematic_b {
+            Some(_) => Vector2D::default(),
+            None => velocity_b.velocity,
+        };
+        let Some(impact) = time_of_impact(
+            &collider_a.bounds(&transform_a),
+            velocity_a.velocity,
+            &collider_b.bounds(&transform_b),
+            motion_b,
+        ) else {
+            continue;
+        };
+        match impacts.last_mut() {
+            Some((entity, other, first)) if *entity == entity_a => {
+                if impact.time < first.time {
+                    (*other, *first) = (entity_b, impact);
+                }
+            }
+            _ => impacts.push((entity_a, entity_b, impact)),
+        }
+    }
+
+    let send_events = world.has_resource::<Events<CollisionEvent>>();
+    for (entity_a, entity_b, impact) in impacts {
+        let kinematic_b = world.has::<Kinematic>(&entity_b);
+        let (mut transform_a, mut velocity_a, body_a, max_speed_a) =
+            world.entry::<Resolvable>(&entity_a);
+        let (mut transform_b, mut velocity_b, body_b, max_speed_b) =
+            world.entry::<Resolvable>(&entity_b);
+        let (before_a, before_b) = (velocity_a.velocity, velocity_b.velocity);
+        if send_events {
+            world.send_event(CollisionEvent {
+                a: entity_a,
+                b: entity_b,
+                normal: impact.normal,
+            });
+        }
+
+        // They're touching, so there's no overlap to correct
+        let touching = SeparationResult {
+            separation: Vector2D::default(),
+            normal: impact.normal,
+            distance: num!(0.),
+        };
+        let impulse = resolve(
+            &touching,
+            num!(0.),
+            (&mut transform_a, &mut velocity_a, &body_a),
+            (&mut transform_b, &mut velocity_b, &body_b),
+        );
+        if impulse {
+            if let Some(max_speed) = max_speed_a {
+                velocity_a.clamp(&max_speed);
+            }
+            if let Some(max_speed) = max_speed_b {
+                velocity_b.clamp(&max_speed);
+            }
+        }
+
+        // Integrating covers the whole frame at the new velocities, so take
+        // off the part of it before the impact (which was at the old ones)
+        transform_a.position += (before_a - velocity_a.velocity) * impact.time;
+        if !kinematic_b {
+            transform_b.position += (before_b - velocity_b.velocity) * impact.time;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agb::fixnum::num;
+
+    use super::{resolve, system_resolve, system_sweep};
+    use crate::ecs::World;
+    use crate::physics::{
+        system_integrate, system_spatial_hash, Collider, CollisionEvent, RigidBody,
+        SeparationResult, SpatialHash, Transform, Velocity,
+    };
+    use crate::types::{Number, Rect, Vector2D};
+
+    // `a` overlapping `b` by `depth` pixels, with `b` to the right
+    fn overlap_x(depth: Number) -> SeparationResult<Number> {
+        SeparationResult {
+            separation: Vector2D::new(depth, num!(0.)),
+            normal: Vector2D::new(num!(1.), num!(0.)),
+            distance: depth,
+        }
+    }
+
+    fn body(inv_mass: Number, bounce: Number, friction: Number) -> RigidBody {
+        RigidBody {
+            inv_mass,
+            bounce,
+            friction,
+        }
+    }
+
+    #[test_case]
+    fn test_restitution(_gba: &mut agb::Gba) {
+        let (mut transform_a, mut transform_b) = (Transform::default(), Transform::default());
+        let mut velocity_a = Velocity::new(Vector2D::new(num!(1.), num!(0.)));
+        let mut velocity_b = Velocity::new(Vector2D::new(num!(-1.), num!(0.)));
+        let elastic = body(num!(1.), num!(1.), num!(0.));
+
+        let impulse = resolve(
+            &overlap_x(num!(2.)),
+            num!(0.),
+            (&mut transform_a, &mut velocity_a, &elastic),
+            (
+                &mut transform_b,
+                &mut velocity_b,
+                &body(num!(1.), num!(0.5), num!(0.)),
+            ),
+        );
+        assert!(impulse);
+        // Equal masses split the overlap, and bounce with the lower restitution
+        assert_eq!(transform_a.position, Vector2D::new(num!(-1.), num!(0.)));
+        assert_eq!(transform_b.position, Vector2D::new(num!(1.), num!(0.)));
+        assert_eq!(velocity_a.velocity, Vector2D::new(num!(-0.5), num!(0.)));
+        assert_eq!(velocity_b.velocity, Vector2D::new(num!(0.5), num!(0.)));
+
+        // Already separating, so only the overlap is corrected
+        let impulse = resolve(
+            &overlap_x(num!(2.)),
+            num!(0.),
+            (&mut transform_a, &mut velocity_a, &elastic),
+            (&mut transform_b, &mut velocity_b, &elastic),
+        );
+        assert!(!impulse);
+        assert_eq!(velocity_a.velocity, Vector2D::new(num!(-0.5), num!(0.)));
+        assert_eq!(transform_a.position, Vector2D::new(num!(-2.), num!(0.)));
+    }
+
+    #[test_case]
+    fn test_static_body(_gba: &mut agb::Gba) {
+        let (mut transform_a, mut transform_b) = (Transform::default(), Transform::default());
+        let mut velocity_a = Velocity::new(Vector2D::new(num!(2.), num!(0.)));
+        let mut velocity_b = Velocity::default();
+        let wall = RigidBody::fixed(num!(1.), num!(0.));
+
+        resolve(
+            &overlap_x(num!(1.)),
+            num!(0.),
+            (
+                &mut transform_a,
+                &mut velocity_a,
+                &body(num!(1.), num!(1.), num!(0.)),
+            ),
+            (&mut transform_b, &mut velocity_b, &wall),
+        );
+        assert_eq!(transform_a.position, Vector2D::new(num!(-1.), num!(0.)));
+        assert_eq!(velocity_a.velocity, Vector2D::new(num!(-2.), num!(0.)));
+        assert_eq!(transform_b, Transform::default());
+        assert_eq!(velocity_b, Velocity::default());
+
+        // Nothing moves two static bodies
+        assert!(!resolve(
+            &overlap_x(num!(1.)),
+            num!(0.),
+            (&mut transform_a, &mut velocity_a, &wall),
+            (&mut transform_b, &mut velocity_b, &wall),
+        ));
+        assert_eq!(transform_a.position, Vector2D::new(num!(-1.), num!(0.)));
+    }
+
+    #[test_case]
+    fn test_friction(_gba: &mut agb::Gba) {
+        let floor = RigidBody::fixed(num!(0.), num!(1.));
+        let land = |friction: Number, velocity: Vector2D<Number>| {
+            let mut transform = Transform::default();
+            let mut velocity = Velocity::new(velocity);
+            resolve(
+                &overlap_x(num!(1.)),
+                num!(0.),
+                (
+                    &mut transform,
+                    &mut velocity,
+                    &body(num!(1.), num!(0.), friction),
+                ),
+                (&mut Transform::default(), &mut Velocity::default(), &floor),
+            );
+            velocity.velocity
+        };
+
+        // Without friction the body keeps sliding
+        assert_eq!(
+            land(num!(0.), Vector2D::new(num!(1.), num!(0.5))),
+            Vector2D::new(num!(0.), num!(0.5))
+        );
+        // Friction (the geometric mean of both bodies') is limited by the
+        // normal impulse...
+        assert_eq!(
+            land(num!(0.0625), Vector2D::new(num!(1.), num!(0.5))),
+            Vector2D::new(num!(0.), num!(0.25))
+        );
+        // ...but never reverses the sliding
+        assert_eq!(
+            land(num!(1.), Vector2D::new(num!(1.), num!(0.5))),
+            Vector2D::new(num!(0.), num!(0.))
+        );
+        assert_eq!(
+            land(num!(1.), Vector2D::new(num!(1.), num!(-0.5))),
+            Vector2D::new(num!(0.), num!(0.))
+        );
+    }
+
+    #[test_case]
+    fn test_slop(_gba: &mut agb::Gba) {
+        let (mut transform_a, mut transform_b) = (Transform::default(), Transform::default());
+        let (mut velocity_a, mut velocity_b) = (Velocity::default(), Velocity::default());
+        let body = body(num!(1.), num!(1.), num!(0.));
+
+        resolve(
+            &overlap_x(num!(0.5)),
+            num!(0.5),
+            (&mut transform_a, &mut velocity_a, &body),
+            (&mut transform_b, &mut velocity_b, &body),
+        );
+        assert_eq!(transform_a.position, Vector2D::new(num!(0.), num!(0.)));
+
+        resolve(
+            &overlap_x(num!(2.5)),
+            num!(0.5),
+            (&mut transform_a, &mut velocity_a, &body),
+            (&mut transform_b, &mut velocity_b, &body),
+        );
+        assert_eq!(transform_a.position, Vector2D::new(num!(-1.), num!(0.)));
+        assert_eq!(transform_b.position, Vector2D::new(num!(1.), num!(0.)));
+    }
+
+    #[test_case]
+    fn test_system_resolve(_gba: &mut agb::Gba) {
+        let square = Collider::new(Rect::new(
+            Vector2D::new(num!(0.), num!(0.)),
+            Vector2D::new(num!(8.), num!(8.)),
+        ));
+        let mut world = World::new();
+        world.add_event::<CollisionEvent>();
+        let ball = world.spawn((
+            Transform::at(Vector2D::new(num!(0.), num!(0.))),
+            Velocity::new(Vector2D::new(num!(1.), num!(0.))),
+            RigidBody::default(),
+            square,
+        ));
+        let wall = world.spawn((
+            Transform::at(Vector2D::new(num!(6.), num!(0.))),
+            Velocity::default(),
+            RigidBody::fixed(num!(1.), num!(0.)),
+            square,
+        ));
+        world.spawn((
+            Transform::at(Vector2D::new(num!(100.), num!(0.))),
+            Velocity::default(),
+            RigidBody::default(),
+            square,
+        ));
+
+        system_resolve(&mut world);
+        assert_eq!(
+            world.entry::<&Velocity>(&ball).velocity,
+            Vector2D::new(num!(-1.), num!(0.))
+        );
+        assert!(world.entry::<&Transform>(&ball).position.x < num!(-1.9));
+        assert_eq!(world.entry::<&Transform>(&wall).position.x, num!(6.));
+        let events = world.events::<CollisionEvent>();
+        assert_eq!(events.len(), 1);
+        let event = events.iter().next().unwrap();
+        assert_eq!((event.a, event.b), (ball, wall));
+    }
+
+    #[test_case]
+    fn test_system_resolve_spatial_hash(_gba: &mut agb::Gba) {
+        let square = Collider::new(Rect::new(
+            Vector2D::new(num!(0.), num!(0.)),
+            Vector2D::new(num!(8.), num!(8.)),
+        ));
+        let mut world = World::new();
+        world.insert_resource(SpatialHash::new(16));
+        let ball = world.spawn((
+            Transform::at(Vector2D::new(num!(2.), num!(0.))),
+            Velocity::default(),
+            RigidBody::default(),
+            square,
+        ));
+        world.spawn((
+            Transform::at(Vector2D::new(num!(6.), num!(0.))),
+            Velocity::default(),
+            RigidBody::fixed(num!(1.), num!(0.)),
+            square,
+        ));
+
+        // Only pairs the hash has seen are checked
+        system_resolve(&mut world);
+        assert_eq!(world.entry::<&Transform>(&ball).position.x, num!(2.));
+        system_spatial_hash(&mut world);
+        system_resolve(&mut world);
+        assert!(world.entry::<&Transform>(&ball).position.x < num!(-1.9));
+    }
+
+    #[test_case]
+    fn test_system_sweep(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let size = Vector2D::new(num!(4.), num!(4.));
+        let wall = (
+            Transform::at(Vector2D::new(num!(10.), num!(-2.))),
+            Velocity::default(),
+            RigidBody::fixed(num!(1.), num!(0.)),
+            Collider::new(Rect::new(
+                Vector2D::new(num!(0.), num!(0.)),
+                Vector2D::new(num!(2.), num!(8.)),
+            )),
+        );
+        let bullet = |collider: Collider| {
+            (
+                Transform::default(),
+                Velocity::new(Vector2D::new(num!(16.), num!(0.))),
+                RigidBody::default(),
+                collider,
+            )
+        };
+        world.spawn(wall);
+        let swept = world.spawn(bullet(
+            Collider::new(Rect::new(Vector2D::new(num!(0.), num!(0.)), size)).with_ccd(),
+        ));
+        let tunnelling = world.spawn(bullet(Collider::new(Rect::new(
+            Vector2D::new(num!(0.), num!(0.)),
+            size,
+        ))));
+
+        system_sweep(&mut world);
+        system_integrate(&mut world);
+
+        // Bounced at x = 6, 0.375 of the way through the frame, then moved
+        // back the other way for the rest of it
+        let (transform, velocity) = world.entry::<(&Transform, &Velocity)>(&swept);
+        assert_eq!(velocity.velocity, Vector2D::new(num!(-16.), num!(0.)));
+        assert_eq!(transform.position, Vector2D::new(num!(-4.), num!(0.)));
+        let transform = *world.entry::<&Transform>(&tunnelling);
+        assert_eq!(transform.position, Vector2D::new(num!(16.), num!(0.)));
+    }
+
+    #[test_case]
+    fn test_system_sweep_moving(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let body = |x: Number, velocity: Number, collider: Collider| {
+            (
+                Transform::at(Vector2D::new(x, num!(0.))),
+                Velocity::new(Vector2D::new(velocity, num!(0.))),
+                RigidBody::default(),
+                collider,
+            )
+        };
+        let rect = Rect::new(
+            Vector2D::new(num!(0.), num!(0.)),
+            Vector2D::new(num!(4.), num!(4.)),
+        );
+        let a = world.spawn(body(num!(0.), num!(16.), Collider::new(rect).with_ccd()));
+        let b = world.spawn(body(num!(16.), num!(-8.), Collider::new(rect)));
+
+        system_sweep(&mut world);
+        system_integrate(&mut world);
+
+        // They meet halfway through the frame with A at x = 8 and B at
+        // x = 12, swap velocities, then move apart for the rest of it
+        let (transform, velocity) = world.entry::<(&Transform, &Velocity)>(&a);
+        assert_eq!(velocity.velocity, Vector2D::new(num!(-8.), num!(0.)));
+        assert_eq!(transform.position, Vector2D::new(num!(4.), num!(0.)));
+        let (transform, velocity) = world.entry::<(&Transform, &Velocity)>(&b);
+        assert_eq!(velocity.velocity, Vector2D::new(num!(16.), num!(0.)));
+        assert_eq!(transform.position, Vector2D::new(num!(20.), num!(0.)));
+    }
+}
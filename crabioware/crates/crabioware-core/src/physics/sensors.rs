@@ -0,0 +1,107 @@
+// Trigger volumes
+//
+// Sensor colliders (see `Collider::with_sensor`) mark areas like pickups,
+// hazards and goals. Nothing pushes them, so they only need a `Transform`
+// and `Collider`, and `system_sensors` reports what overlaps them,
+//
+//     world.add_event::<SensorEvent>();
+//     ...
+//     system_sensors(&mut world);
+//     for event in world.events::<SensorEvent>().iter() { ... }
+use super::body::{Collider, Transform};
+use super::events::SensorEvent;
+use super::intersect::Intersects;
+use super::spatial::SpatialHash;
+use crate::ecs::{EntityId, Events, World};
+
+// Send a `SensorEvent` (if the world has that event channel) for each sensor
+// overlapping another collider it meets. When two sensors overlap, only one
+// event is sent. If the world has a `SpatialHash`, only its candidate pairs
+// are checked.
+pub fn system_sensors(world: &mut World) {
+    type Body<'a> = (EntityId, &'a Transform, &'a Collider);
+
+    if !world.has_resource::<Events<SensorEvent>>() {
+        return;
+    }
+    let pairs = world.resource::<SpatialHash>().map(|hash| hash.pairs());
+    let candidates = pairs
+        .as_deref()
+        .map(|pairs| world.candidates::<Body, Body>(pairs));
+    let everything = candidates.is_none().then(|| world.combinations::<Body>());
+    for ((entity_a, transform_a, collider_a), (entity_b, transform_b, collider_b)) in candidates
+        .into_iter()
+        .flatten()
+        .chain(everything.into_iter().flatten())
+    {
+        if !(collider_a.sensor || collider_b.sensor) || !collider_a.meets(&collider_b) {
+            continue;
+        }
+        let bounds_a = collider_a.bounds(&transform_a);
+        if bounds_a
+            .intersection(&collider_b.bounds(&transform_b))
+            .is_none()
+        {
+            continue;
+        }
+
+        let (sensor, other) = match collider_a.sensor {
+            true => (entity_a, entity_b),
+            false => (entity_b, entity_a),
+        };
+        world.send_event(SensorEvent { sensor, other });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agb::fixnum::num;
+
+    use super::system_sensors;
+    use crate::ecs::World;
+    use crate::physics::{system_resolve, Collider, RigidBody, SensorEvent, Transform, Velocity};
+    use crate::types::{Rect, Vector2D};
+
+    #[test_case]
+    fn test_system_sensors(_gba: &mut agb::Gba) {
+        let square = Collider::new(Rect::new(
+            Vector2D::new(num!(0.), num!(0.)),
+            Vector2D::new(num!(8.), num!(8.)),
+        ));
+        let mut world = World::new();
+        world.add_event::<SensorEvent>();
+        let player = world.spawn((
+            Transform::at(Vector2D::new(num!(0.), num!(0.))),
+            Velocity::default(),
+            RigidBody::default(),
+            square,
+        ));
+        let berry = world.spawn((
+            Transform::at(Vector2D::new(num!(4.), num!(4.))),
+            square.with_sensor(),
+        ));
+        // Overlapping too, but on a layer the berry doesn't look for
+        world.spawn((
+            Transform::at(Vector2D::new(num!(2.), num!(2.))),
+            square.with_layers(0b10, 0b10),
+        ));
+        // Far away
+        world.spawn((
+            Transform::at(Vector2D::new(num!(100.), num!(0.))),
+            square.with_sensor(),
+        ));
+
+        system_resolve(&mut world);
+        system_sensors(&mut world);
+
+        // The player overlaps the berry, but isn't pushed out of it
+        assert_eq!(
+            world.entry::<&Transform>(&player).position,
+            Vector2D::new(num!(0.), num!(0.))
+        );
+        let events = world.events::<SensorEvent>();
+        assert_eq!(events.len(), 1);
+        let event = events.iter().next().unwrap();
+        assert_eq!((event.sensor, event.other), (berry, player));
+    }
+}
@@ -0,0 +1,178 @@
+// Broad phase collision detection
+//
+// Testing every pair of colliders takes time quadratic in their number,
+// which is too slow once a game has dozens of bullets. A `SpatialHash`
+// buckets colliders into a uniform grid over the screen, and only those
+// sharing a cell become candidate pairs for the narrow phase,
+//
+//     world.insert_resource(SpatialHash::new(16));
+//     ...
+//     system_integrate(&mut world);
+//     system_spatial_hash(&mut world);
+//     let pairs = world.resource::<SpatialHash>().unwrap().pairs();
+//     for (a, b) in world.candidates::<Body, Body>(&pairs) { ... }
+//
+// `system_resolve` does this itself when the world has a `SpatialHash`.
+// Colliders off screen are put in the nearest cells on its edge, so they
+// still collide (just less efficiently).
+use agb::display::{HEIGHT as GBA_HEIGHT, WIDTH as GBA_WIDTH};
+use alloc::vec::Vec;
+
+use super::body::{Collider, Transform};
+use crate::ecs::{EntityId, World};
+use crate::types::{Number, Rect, Vector2D};
+
+pub struct SpatialHash {
+    cell_size: i32,
+    columns: i32,
+    rows: i32,
+    // Entities overlapping each cell (row by row), with the top left cell
+    // they overlap
+    cells: Vec<Vec<(EntityId, Vector2D<i32>)>>,
+}
+impl SpatialHash {
+    // Square cells of `cell_size` pixels, ideally about the size of the
+    // colliders, covering the screen
+    pub fn new(cell_size: i32) -> SpatialHash {
+        let columns = (GBA_WIDTH + cell_size - 1) / cell_size;
+        let rows = (GBA_HEIGHT + cell_size - 1) / cell_size;
+        Self {
+            cell_size,
+            columns,
+            rows,
+            cells: (0..columns * rows).map(|_| Vec::new()).collect(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for cell in self.cells.iter_mut() {
+            cell.clear();
+        }
+    }
+
+    fn cell(&self, point: Vector2D<Number>) -> Vector2D<i32> {
+        Vector2D {
+            x: (point.x.floor() / self.cell_size).clamp(0, self.columns - 1),
+            y: (point.y.floor() / self.cell_size).clamp(0, self.rows - 1),
+        }
+    }
+
+    // Add an entity to every cell its bounds overlap
+    pub fn insert(&mut self, entity: EntityId, bounds: Rect<Number>) {
+        let min = self.cell(bounds.position);
+        let max = self.cell(bounds.position + bounds.size);
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                self.cells[(y * self.columns + x) as usize].push((entity, min));
+            }
+        }
+    }
+
+    // Every pair of entities sharing a cell, once each
+    pub fn pairs(&self) -> Vec<(EntityId, EntityId)> {
+        let mut pairs = Vec::new();
+        for (index, cell) in self.cells.iter().enumerate() {
+            let here = Vector2D {
+                x: index as i32 % self.columns,
+                y: index as i32 / self.columns,
+            };
+            for (i, (a, min_a)) in cell.iter().enumerate() {
+                for (b, min_b) in cell[i + 1..].iter() {
+                    // Entities in several cells together are only paired in
+                    // the top left one they share
+                    let first = Vector2D {
+                        x: min_a.x.max(min_b.x),
+                        y: min_a.y.max(min_b.y),
+                    };
+                    if first == here {
+                        pairs.push((*a, *b));
+                    }
+                }
+            }
+        }
+        pairs
+    }
+}
+
+// Rebuild the world's `SpatialHash` (if it has one) from every `Collider`.
+// Run it after moving things, and before using the hash for collisions.
+pub fn system_spatial_hash(world: &mut World) {
+    let Some(mut hash) = world.resource_mut::<SpatialHash>() else {
+        return;
+    };
+    hash.clear();
+    for (entity, transform, collider) in world.components::<(EntityId, &Transform, &Collider)>() {
+        hash.insert(entity, collider.bounds(&transform));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agb::fixnum::num;
+    use alloc::vec::Vec;
+
+    use super::{system_spatial_hash, SpatialHash};
+    use crate::ecs::{EntityId, World};
+    use crate::physics::{Collider, Transform};
+    use crate::types::{Rect, Vector2D};
+
+    fn sorted(pairs: &[(EntityId, EntityId)]) -> Vec<(EntityId, EntityId)> {
+        let mut pairs: Vec<_> = pairs
+            .iter()
+            .map(|(a, b)| ((*a).min(*b), (*a).max(*b)))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    #[test_case]
+    fn test_spatial_hash_pairs(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let [a, b, c, d, e] = [(); 5].map(|_| world.spawn(Transform::default()));
+        let size = Vector2D::new(num!(8.), num!(8.));
+
+        let mut hash = SpatialHash::new(16);
+        // a and b both straddle the same four cells, c shares one with them
+        hash.insert(a, Rect::new(Vector2D::new(num!(12.), num!(12.)), size));
+        hash.insert(b, Rect::new(Vector2D::new(num!(10.), num!(14.)), size));
+        hash.insert(c, Rect::new(Vector2D::new(num!(20.), num!(20.)), size));
+        // d is far away, and e is off the edge of the screen beside it
+        hash.insert(d, Rect::new(Vector2D::new(num!(230.), num!(100.)), size));
+        hash.insert(e, Rect::new(Vector2D::new(num!(300.), num!(100.)), size));
+
+        assert_eq!(
+            sorted(&hash.pairs()),
+            sorted(&[(a, b), (a, c), (b, c), (d, e)])
+        );
+
+        hash.clear();
+        assert!(hash.pairs().is_empty());
+    }
+
+    #[test_case]
+    fn test_system_spatial_hash(_gba: &mut agb::Gba) {
+        let mut world = World::new();
+        let collider = Collider::new(Rect::new(
+            Vector2D::new(num!(0.), num!(0.)),
+            Vector2D::new(num!(8.), num!(8.)),
+        ));
+        let a = world.spawn((Transform::at(Vector2D::new(num!(0.), num!(0.))), collider));
+        let b = world.spawn((Transform::at(Vector2D::new(num!(4.), num!(4.))), collider));
+        world.spawn((
+            Transform::at(Vector2D::new(num!(100.), num!(100.))),
+            collider,
+        ));
+
+        // Nothing to rebuild without a hash
+        system_spatial_hash(&mut world);
+
+        world.insert_resource(SpatialHash::new(32));
+        system_spatial_hash(&mut world);
+        let pairs = world.resource::<SpatialHash>().unwrap().pairs();
+        assert_eq!(sorted(&pairs), sorted(&[(a, b)]));
+
+        world.entry::<&mut Transform>(&b).position = Vector2D::new(num!(200.), num!(0.));
+        system_spatial_hash(&mut world);
+        assert!(world.resource::<SpatialHash>().unwrap().pairs().is_empty());
+    }
+}
@@ -0,0 +1,202 @@
+// Tile grids
+//
+// Games describe the collision of their levels as a grid of square tiles,
+// each either solid or not, by implementing `TileGrid`. The top left tile,
+// (0, 0), starts at the origin of screen space.
+//
+// A `TileCollider` moves an entity's `Collider` through the grid one axis
+// at a time, so it slides along walls rather than sticking to them,
+//
+//     let blocked = TileCollider::new(&level).move_by(&mut transform, &collider, motion);
+//     if blocked.y {
+//         velocity.velocity.y = num!(0.);
+//     }
+//
+// Only solid tiles are supported for now. One-way platforms and slopes
+// would need `TileGrid` to say what kind of tile it has, rather than just
+// whether it's solid, and `move_by` to handle each kind.
+use agb::fixnum::num;
+
+use super::body::{Collider, Transform};
+use crate::types::{Number, Vector2D};
+
+pub trait TileGrid {
+    // Width (and height) of a tile, in pixels
+    fn tile_size(&self) -> i32;
+
+    // Whether the tile at (column, row) blocks movement. Tiles off the edges
+    // of the grid (including negative ones) are asked about too, so the game
+    // decides whether the edges are walls.
+    fn is_solid(&self, tile: Vector2D<i32>) -> bool;
+
+    // The tile containing a point
+    fn tile_at(&self, point: Vector2D<Number>) -> Vector2D<i32> {
+        let size = self.tile_size();
+        Vector2D {
+            x: point.x.floor().div_euclid(size),
+            y: point.y.floor().div_euclid(size),
+        }
+    }
+}
+
+// The first tile a (top or left) edge is in
+fn first_tile(edge: Number, size: i32) -> i32 {
+    edge.floor().div_euclid(size)
+}
+
+// The last tile a (bottom or right) edge is in, as it's just past the box
+fn last_tile(edge: Number, size: i32) -> i32 {
+    (edge - Number::from_raw(1)).floor().div_euclid(size)
+}
+
+// Which axes were stopped by a solid tile during a move
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Blocked {
+    pub x: bool,
+    pub y: bool,
+}
+
+pub struct TileCollider<'g, G: TileGrid> {
+    grid: &'g G,
+}
+impl<'g, G: TileGrid> TileCollider<'g, G> {
+    pub fn new(grid: &'g G) -> TileCollider<'g, G> {
+        Self { grid }
+    }
+
+    // How far a box spanning `min` to `max` on one axis can move before
+    // entering a line (column or row) of tiles with a solid one across it,
+    // or None if it can move all the way
+    fn clearance(
+        &self,
+        min: Number,
+        max: Number,
+        motion: Number,
+        solid: impl Fn(i32) -> bool,
+    ) -> Option<Number> {
+        let size = self.grid.tile_size();
+        if motion > num!(0.) {
+            let (next, last) = (last_tile(max, size) + 1, last_tile(max + motion, size));
+            (next..=last)
+                .find(|line| solid(*line))
+                .map(|line| Number::new(line * size) - max)
+        } else if motion < num!(0.) {
+            let (next, last) = (first_tile(min, size) - 1, first_tile(min + motion, size));
+            (last..=next)
+                .rev()
+                .find(|line| solid(*line))
+                .map(|line| Number::new((line + 1) * size) - min)
+        } else {
+            None
+        }
+    }
+
+    // Move horizontally then vertically, each time stopping flush against
+    // the first solid tiles the collider would enter. Tiles it's already
+    // overlapping don't block it.
+    pub fn move_by(
+        &self,
+        transform: &mut Transform,
+        collider: &Collider,
+        motion: Vector2D<Number>,
+    ) -> Blocked {
+        let size = self.grid.tile_size();
+        let is_solid = |x, y| self.grid.is_solid(Vector2D { x, y });
+
+        let bounds = collider.bounds(transform);
+        let (min, max) = (bounds.position, bounds.position + bounds.size);
+        let rows = first_tile(min.y, size)..=last_tile(max.y, size);
+        let x = self.clearance(min.x, max.x, motion.x, |column| {
+            rows.clone().any(|row| is_solid(column, row))
+        });
+        transform.position.x += x.unwrap_or(motion.x);
+
+        let bounds = collider.bounds(transform);
+        let (min, max) = (bounds.position, bounds.position + bounds.size);
+        let columns = first_tile(min.x, size)..=last_tile(max.x, size);
+        let y = self.clearance(min.y, max.y, motion.y, |row| {
+            columns.clone().any(|column| is_solid(column, row))
+        });
+        transform.position.y += y.unwrap_or(motion.y);
+
+        Blocked {
+            x: x.is_some(),
+            y: y.is_some(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agb::fixnum::num;
+
+    use super::{Blocked, TileCollider, TileGrid};
+    use crate::physics::{Collider, Transform};
+    use crate::types::{Rect, Vector2D};
+
+    // 8 pixel tiles, with a floor along row 4 and a wall up column 6
+    struct Room;
+    impl TileGrid for Room {
+        fn tile_size(&self) -> i32 {
+            8
+        }
+
+        fn is_solid(&self, tile: Vector2D<i32>) -> bool {
+            tile.y == 4 || tile.x == 6
+        }
+    }
+
+    #[test_case]
+    fn test_tile_collider(_gba: &mut agb::Gba) {
+        let tiles = TileCollider::new(&Room);
+        let collider = Collider::new(Rect::new(
+            Vector2D::new(num!(-4.), num!(-4.)),
+            Vector2D::new(num!(8.), num!(8.)),
+        ));
+
+        // Falls onto the floor, sliding right as it goes
+        let mut transform = Transform::at(Vector2D::new(num!(12.), num!(20.)));
+        let blocked = tiles.move_by(
+            &mut transform,
+            &collider,
+            Vector2D::new(num!(3.), num!(10.)),
+        );
+        assert_eq!(blocked, Blocked { x: false, y: true });
+        assert_eq!(transform.position, Vector2D::new(num!(15.), num!(28.)));
+
+        // Slides along the floor into the wall, then stays there
+        let blocked = tiles.move_by(
+            &mut transform,
+            &collider,
+            Vector2D::new(num!(40.), num!(0.)),
+        );
+        assert_eq!(blocked, Blocked { x: true, y: false });
+        assert_eq!(transform.position, Vector2D::new(num!(44.), num!(28.)));
+        let blocked = tiles.move_by(
+            &mut transform,
+            &collider,
+            Vector2D::new(num!(0.5), num!(0.5)),
+        );
+        assert_eq!(blocked, Blocked { x: true, y: true });
+        assert_eq!(transform.position, Vector2D::new(num!(44.), num!(28.)));
+
+        // Free to move away
+        let blocked = tiles.move_by(
+            &mut transform,
+            &collider,
+            Vector2D::new(num!(-2.5), num!(-8.)),
+        );
+        assert_eq!(blocked, Blocked::default());
+        assert_eq!(transform.position, Vector2D::new(num!(41.5), num!(20.)));
+
+        // Flush against the other side of the wall, so it can't move left
+        let mut transform = Transform::at(Vector2D::new(num!(60.), num!(20.)));
+        let blocked = tiles.move_by(
+            &mut transform,
+            &collider,
+            Vector2D::new(num!(-20.), num!(0.)),
+        );
+        assert_eq!(blocked, Blocked { x: true, y: false });
+        assert_eq!(transform.position, Vector2D::new(num!(60.), num!(20.)));
+    }
+}
@@ -1,4 +1,3 @@
-use agb::fixnum::FixedWidthUnsignedInteger;
 use agb::fixnum::Number as AGBNumber;
 pub use agb::fixnum::Rect;
 pub use agb::fixnum::Vector2D;
@@ -12,6 +11,9 @@ pub type Number = agb::fixnum::FixedNum<10>;
 // ========================================================================== //
 pub trait VecMath<N> {
     fn dot(&self, other: Self) -> N;
+    // z of the 3D cross product, positive when `other` is clockwise of
+    // `self` on screen (y points down)
+    fn cross(&self, other: Self) -> N;
 }
 impl<N> VecMath<N> for Vector2D<N>
 where
@@ -20,6 +22,10 @@ where
     fn dot(&self, other: Self) -> N {
         self.x * other.x + self.y * other.y
     }
+
+    fn cross(&self, other: Self) -> N {
+        self.x * other.y - self.y * other.x
+    }
 }
 
 // FIXME: add AABB { center: ..., half_width: ...}
@@ -55,19 +61,167 @@ impl<const N: usize> RectMath<FixedNum<N>> for Rect<FixedNum<N>> {
     }
 }
 
+// ========================================================================== //
+// Circle
+// ========================================================================== //
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Circle<N>
+where
+    N: AGBNumber,
+{
+    pub center: Vector2D<N>,
+    pub radius: N,
+}
+impl<N> Circle<N>
+where
+    N: AGBNumber,
+{
+    pub fn new(center: Vector2D<N>, radius: N) -> Self {
+        Circle { center, radius }
+    }
+
+    pub fn translate(&self, offset: Vector2D<N>) -> Self {
+        Circle {
+            center: self.center + offset,
+            radius: self.radius,
+        }
+    }
+
+    // Smallest rectangle containing the circle
+    pub fn bounding_rect(&self) -> Rect<N> {
+        let radius = Vector2D {
+            x: self.radius,
+            y: self.radius,
+        };
+        Rect::new(self.center - radius, radius + radius)
+    }
+}
+
+// ========================================================================== //
+// Ray
+// ========================================================================== //
+// A half line from `origin`, in the (unit) `direction`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ray<N>
+where
+    N: AGBNumber,
+{
+    pub origin: Vector2D<N>,
+    pub direction: Vector2D<N>,
+}
+impl<const N: usize> Ray<FixedNum<N>> {
+    // `direction` is normalised, so mustn't be zero
+    pub fn new(origin: Vector2D<FixedNum<N>>, direction: Vector2D<FixedNum<N>>) -> Self {
+        Ray {
+            origin,
+            direction: direction.normalise(),
+        }
+    }
+
+    // The point `distance` along the ray
+    pub fn at(&self, distance: FixedNum<N>) -> Vector2D<FixedNum<N>> {
+        self.origin + self.direction * distance
+    }
+}
+
+// ========================================================================== //
+// OrientedRect
+// ========================================================================== //
+// An oriented bounding box (OBB), a rectangle of `2 * half_size` rotated by
+// `angle` (in turns, like `AffineMatrix::from_rotation`) about its center
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrientedRect<N>
+where
+    N: AGBNumber,
+{
+    pub center: Vector2D<N>,
+    pub half_size: Vector2D<N>,
+    pub angle: N,
+}
+impl<N> OrientedRect<N>
+where
+    N: AGBNumber,
+{
+    pub fn new(center: Vector2D<N>, half_size: Vector2D<N>, angle: N) -> Self {
+        OrientedRect {
+            center,
+            half_size,
+            angle,
+        }
+    }
+
+    pub fn translate(&self, offset: Vector2D<N>) -> Self {
+        OrientedRect {
+            center: self.center + offset,
+            ..*self
+        }
+    }
+}
+impl<const N: usize> OrientedRect<FixedNum<N>> {
+    // `rect` rotated about its center
+    pub fn from_rect(rect: Rect<FixedNum<N>>, angle: FixedNum<N>) -> Self {
+        Self::new(rect.centroid(), rect.size / 2, angle)
+    }
+
+    // The directions of the rectangle's width and height
+    pub fn axes(&self) -> [Vector2D<FixedNum<N>>; 2] {
+        let (cos, sin) = (self.angle.cos(), self.angle.sin());
+        [Vector2D { x: cos, y: sin }, Vector2D { x: -sin, y: cos }]
+    }
+
+    // In order around the rectangle, starting from the (unrotated) top left
+    pub fn corners(&self) -> [Vector2D<FixedNum<N>>; 4] {
+        let [x, y] = self.axes();
+        let (x, y) = (x * self.half_size.x, y * self.half_size.y);
+        [
+            self.center - x - y,
+            self.center + x - y,
+            self.center + x + y,
+            self.center - x + y,
+        ]
+    }
+
+    // Smallest (axis aligned) rectangle containing the rotated one
+    pub fn bounding_rect(&self) -> Rect<FixedNum<N>> {
+        let [x, y] = self.axes();
+        let extent = Vector2D {
+            x: (x.x * self.half_size.x).abs() + (y.x * self.half_size.y).abs(),
+            y: (x.y * self.half_size.x).abs() + (y.y * self.half_size.y).abs(),
+        };
+        Rect::new(self.center - extent, extent + extent)
+    }
+}
+
 // ========================================================================== //
 // Polygon
 // ========================================================================== //
+// Vertices in order around the polygon (either way). Collisions assume it's
+// convex.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Polygon<N>
 where
-    N: FixedWidthUnsignedInteger,
+    N: AGBNumber,
 {
     pub vertices: Vec<Vector2D<N>>,
 }
+impl<N> Polygon<N>
+where
+    N: AGBNumber,
+{
+    pub fn new(vertices: Vec<Vector2D<N>>) -> Self {
+        Polygon { vertices }
+    }
+
+    pub fn translate(&self, offset: Vector2D<N>) -> Self {
+        Polygon {
+            vertices: self.vertices.iter().map(|v| *v + offset).collect(),
+        }
+    }
+}
 
 impl<N> IntoIterator for Polygon<N>
 where
-    N: FixedWidthUnsignedInteger,
+    N: AGBNumber,
 {
     type Item = Vector2D<N>;
     type IntoIter = PolygonIntoIter<N>;
@@ -84,7 +238,7 @@ where
 
 pub struct PolygonIntoIter<N>
 where
-    N: FixedWidthUnsignedInteger,
+    N: AGBNumber,
 {
     polygon: Polygon<N>,
     index: usize,
@@ -92,7 +246,7 @@ where
 }
 impl<N> Iterator for PolygonIntoIter<N>
 where
-    N: FixedWidthUnsignedInteger,
+    N: AGBNumber,
 {
     type Item = Vector2D<N>;
 
@@ -0,0 +1,110 @@
+// Derive macros for crabioware-core
+//
+// `#[derive(Component)]` implements `crabioware_core::ecs::Component`.
+// Components use dense storage unless they ask for sparse storage,
+//
+//     #[derive(Component)]
+//     #[component(storage = "sparse")]
+//     struct HeadComponent;
+//
+// Components don't need to be registered with the `World` before use. They
+// register themselves the first time they're inserted into an entity.
+//
+// `#[derive(Bundle)]` implements `crabioware_core::ecs::Bundle` for a struct
+// whose fields are all bundles (components, tuples, or other bundles),
+//
+//     #[derive(Bundle)]
+//     struct Body {
+//         tile: TileComponent,
+//         sprite: SpriteComponent,
+//     }
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Index, LitStr};
+
+#[proc_macro_derive(Component, attributes(component))]
+pub fn derive_component(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_component(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand_component(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut storage = quote! { Dense };
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("component"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("storage") {
+                return Err(meta.error("unsupported component attribute"));
+            }
+            let value: LitStr = meta.value()?.parse()?;
+            storage = match value.value().as_str() {
+                "dense" => quote! { Dense },
+                "sparse" => quote! { Sparse },
+                _ => {
+                    return Err(syn::Error::new(
+                        value.span(),
+                        "expected storage = \"dense\" or \"sparse\"",
+                    ))
+                }
+            };
+            Ok(())
+        })?;
+    }
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::crabioware_core::ecs::Component for #name #type_generics #where_clause {
+            const STORAGE: ::crabioware_core::ecs::StorageKind =
+                ::crabioware_core::ecs::StorageKind::#storage;
+        }
+    })
+}
+
+#[proc_macro_derive(Bundle)]
+pub fn derive_bundle(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_bundle(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand_bundle(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "Bundle can only be derived for structs",
+            ))
+        }
+    };
+    // Named fields, or indexes of tuple struct fields
+    let members = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => quote! { #ident },
+            None => {
+                let index = Index::from(index);
+                quote! { #index }
+            }
+        });
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::crabioware_core::ecs::Bundle for #name #type_generics #where_clause {
+            fn insert_into(self, builder: &mut ::crabioware_core::ecs::EntityBuilder) {
+                #(::crabioware_core::ecs::Bundle::insert_into(self.#members, builder);)*
+            }
+        }
+    })
+}
@@ -125,11 +125,26 @@ mod tiled_export {
             })
             .collect();
 
+        // Every object by type, for spawning prefabs
+        let objects = level
+            .layers
+            .iter()
+            .filter_map(|layer| layer.objects.as_ref())
+            .flatten()
+            .map(|obj| format!("({:?}, {}, {})", obj.object_name, obj.x, obj.y))
+            .collect::<Vec<String>>()
+            .join(", ");
+
         writeln!(&mut writer, "// AUTO-GENERATED")?;
         writeln!(&mut writer, "// Level data for {filename}")?;
         writeln!(&mut writer, "const WIDTH: u32 = {};", level.width)?;
         writeln!(&mut writer, "const HEIGHT: u32 = {};", level.height)?;
 
+        writeln!(
+            &mut writer,
+            "const OBJECTS: &[(&str, i32, i32)] = &[{objects}];"
+        )?;
+
         writeln!(&mut writer, "// Tilemap layers")?;
         for (name, data) in tile_layers.iter() {
             let varname = name.to_uppercase();
@@ -179,6 +194,7 @@ pub const fn get_level() -> Level {{
         berries: POINTS_BERRY,
         doors: POINTS_DOOR,
         warps: POINTS_WARP,
+        objects: OBJECTS,
     }}
 }}
 "#
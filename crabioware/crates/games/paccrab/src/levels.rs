@@ -1,5 +1,6 @@
 use agb::display::tiled::{TileSet, TileSetting, VRamManager};
 use agb::{fixnum::Vector2D, include_background_gfx};
+use crabioware_core::physics::TileGrid;
 
 include_background_gfx!(tile_sheet, "000000", tiles => "assets/tiles.png");
 
@@ -14,6 +15,8 @@ pub struct Level {
     pub berries: &'static [(i32, i32)],
     pub doors: &'static [(i32, i32)],
     pub warps: &'static [(i32, i32)],
+    // Every object in the level as (Tiled object type, x, y)
+    pub objects: &'static [(&'static str, i32, i32)],
 }
 impl Level {
     pub fn get_tileset(&self) -> &TileSet<'_> {
@@ -29,6 +32,23 @@ impl Level {
     }
 }
 
+// Every tile drawn on the background is a wall. Off the edges of the level
+// is open, for the warps.
+impl TileGrid for Level {
+    fn tile_size(&self) -> i32 {
+        8
+    }
+
+    fn is_solid(&self, tile: Vector2D<i32>) -> bool {
+        let (width, height) = (self.dimensions.x as i32, self.dimensions.y as i32);
+        if tile.x < 0 || tile.y < 0 || tile.x >= width || tile.y >= height {
+            return false;
+        }
+        // Tile 1 is the empty background
+        self.walls[(tile.y * width + tile.x) as usize] != 1
+    }
+}
+
 pub enum Levels {
     LEVEL_1,
 }
@@ -5,52 +5,53 @@ use agb::input::{Button, ButtonController};
 use agb::println;
 
 use agb::rng::RandomNumberGenerator;
-use crabioware_core::ecs::{EntityId, World};
+use crabioware_core::ecs::{Bundle, EntityId, Prefab, Prefabs, With, World};
 use crabioware_core::games::{Game, GameDifficulty, GameState, Games};
 use crabioware_core::graphics::{GraphicsResource, Mode0TileMap, TileMapResource, TileMode};
+use crabioware_core::physics::{
+    Blocked, Collider, Kinematic, TileCollider, TileGrid, Transform, Velocity,
+};
 use crabioware_core::types::{Number, Rect};
 
-use super::components::{
-    CollisionComponent, Direction, DirectionComponent, LocationComponent, SpriteComponent,
-    VelocityComponent,
-};
+use super::components::{Direction, DirectionComponent, PlayerComponent, SpriteComponent};
 use super::graphics::SpriteTag;
 use super::levels::{Level, Levels};
 
+#[derive(Bundle)]
 struct Crab {
-    location: LocationComponent,
+    player: PlayerComponent,
+    // The crab moves in its direction at its velocity's speed on that axis
+    kinematic: Kinematic,
+    transform: Transform,
     direction: DirectionComponent,
-    velocity: VelocityComponent,
-    collision: CollisionComponent,
+    velocity: Velocity,
+    collider: Collider,
     sprite: SpriteComponent,
 }
-impl Crab {
-    fn new(x: Number, y: Number) -> Self {
+impl Prefab for Crab {
+    fn at(location: Vector2D<Number>) -> Self {
         Crab {
-            location: LocationComponent {
-                location: Vector2D { x, y },
-            },
+            player: PlayerComponent,
+            kinematic: Kinematic,
+            transform: Transform::at(location),
             direction: DirectionComponent {
                 direction: Direction::RIGHT,
             },
-            velocity: VelocityComponent {
-                velocity: Vector2D {
-                    x: num!(0.5),
-                    y: num!(0.5),
+            velocity: Velocity::new(Vector2D {
+                x: num!(0.5),
+                y: num!(0.5),
+            }),
+            // The tile the crab is centered on
+            collider: Collider::new(Rect {
+                position: Vector2D {
+                    x: (-4).into(),
+                    y: (-4).into(),
                 },
-            },
-            collision: CollisionComponent {
-                collision: Rect {
-                    position: Vector2D {
-                        x: 4.into(),
-                        y: 4.into(),
-                    },
-                    size: Vector2D {
-                        x: 2.into(),
-                        y: 8.into(),
-                    },
+                size: Vector2D {
+                    x: 8.into(),
+                    y: 8.into(),
                 },
-            },
+            }),
             sprite: SpriteComponent {
                 tag: SpriteTag::Crab,
                 offset: Vector2D {
@@ -61,16 +62,6 @@ impl Crab {
             },
         }
     }
-    fn create(self, world: &mut World) -> EntityId {
-        world
-            .create()
-            .with(self.sprite)
-            .with(self.location)
-            .with(self.direction)
-            .with(self.velocity)
-            .with(self.collision)
-            .build()
-    }
 }
 
 pub struct PacCrabGame<'g> {
@@ -83,15 +74,26 @@ pub struct PacCrabGame<'g> {
 impl<'g> PacCrabGame<'g> {
     pub fn new(_: &GameDifficulty, _: &mut RandomNumberGenerator) -> Self {
         let mut world = World::new();
-        world.register_component::<LocationComponent>();
-        world.register_component::<VelocityComponent>();
+        world.register_component::<Transform>();
+        world.register_component::<Velocity>();
         world.register_component::<DirectionComponent>();
-        world.register_component::<CollisionComponent>();
+        world.register_component::<Collider>();
         world.register_component::<SpriteComponent>();
 
         let level = Levels::LEVEL_1.get_level();
-        let player =
-            Crab::new(Number::new(level.spawn.0), Number::new(level.spawn.1)).create(&mut world);
+        // FIXME: add prefabs for ghosts, berries, etc.
+        let prefabs = Prefabs::new().with::<Crab>("Spawn");
+        for &(object_type, x, y) in level.objects {
+            let position = Vector2D {
+                x: Number::new(x),
+                y: Number::new(y),
+            };
+            prefabs.spawn(&mut world, object_type, position);
+        }
+        let player = world
+            .query::<EntityId, _>(&With::<PlayerComponent>::new())
+            .next()
+            .expect("Level must have a spawn point");
 
         Self {
             world,
@@ -125,43 +127,73 @@ impl<'g> PacCrabGame<'g> {
 
     fn system_player(&self, _time: i32, buttons: &ButtonController) {
         println!("GRABBING COMPONENTS");
-        let (mut location, mut direction, velocity, _collision) =
-            *self.world.entry::<(
-                &mut LocationComponent,
-                &mut DirectionComponent,
-                &VelocityComponent,
-                &CollisionComponent,
-            )>(&self.player);
+        let (mut location, mut direction, velocity, collision) = self.world.entry::<(
+            &mut Transform,
+            &mut DirectionComponent,
+            &Velocity,
+            &Collider,
+        )>(&self.player);
 
         println!("GETTING DIRECTION");
 
-        if buttons.is_pressed(Button::LEFT) {
-            direction.direction = Direction::LEFT;
+        let turn = if buttons.is_pressed(Button::LEFT) {
+            Some(Direction::LEFT)
         } else if buttons.is_pressed(Button::RIGHT) {
-            direction.direction = Direction::RIGHT;
+            Some(Direction::RIGHT)
         } else if buttons.is_pressed(Button::UP) {
-            direction.direction = Direction::UP;
+            Some(Direction::UP)
         } else if buttons.is_pressed(Button::DOWN) {
-            direction.direction = Direction::DOWN;
-        }
+            Some(Direction::DOWN)
+        } else {
+            None
+        };
 
         println!("MOVING");
-        match direction.direction {
-            Direction::RIGHT => {
-                location.location.x += velocity.velocity.x;
-            }
-            Direction::LEFT => {
-                location.location.x -= velocity.velocity.x;
-            }
-            Direction::UP => {
-                location.location.y -= velocity.velocity.y;
-            }
-            Direction::DOWN => {
-                location.location.y += velocity.velocity.y;
-            }
+        steer(
+            &self.level,
+            &mut location,
+            &collision,
+            velocity.velocity,
+            &mut direction.direction,
+            turn,
+        );
+    }
+}
+
+// How far the crab moves in a frame going in `direction`
+fn motion(direction: Direction, speed: Vector2D<Number>) -> Vector2D<Number> {
+    match direction {
+        Direction::RIGHT => Vector2D::new(speed.x, num!(0.)),
+        Direction::LEFT => Vector2D::new(-speed.x, num!(0.)),
+        Direction::UP => Vector2D::new(num!(0.), -speed.y),
+        Direction::DOWN => Vector2D::new(num!(0.), speed.y),
+    }
+}
+
+// Move the crab for a frame, taking the `turn` only if nothing blocks it.
+// Otherwise it carries on the way it was going, so a turn held before the
+// crab lines up with a corridor is taken as soon as it does.
+fn steer<G: TileGrid>(
+    grid: &G,
+    transform: &mut Transform,
+    collider: &Collider,
+    speed: Vector2D<Number>,
+    direction: &mut Direction,
+    turn: Option<Direction>,
+) {
+    let tiles = TileCollider::new(grid);
+    if let Some(turn) = turn {
+        let mut turned = *transform;
+        if tiles.move_by(&mut turned, collider, motion(turn, speed)) == Blocked::default() {
+            *direction = turn;
+            *transform = turned;
+            return;
         }
     }
+    // Walls stop the crab until it turns
+    tiles.move_by(transform, collider, motion(*direction, speed));
 }
+
 impl<'g> Game<'g> for PacCrabGame<'g> {
     fn renderer(&self) -> TileMode {
         TileMode::Mode0
@@ -188,6 +220,7 @@ impl<'g> Game<'g> for PacCrabGame<'g> {
 
     fn advance(&mut self, time: i32, buttons: &ButtonController) -> GameState {
         self.time += time;
+        self.world.advance_tick();
         println!("RUNNING PACCRAB");
 
         self.system_player(time, buttons);
@@ -208,15 +241,12 @@ impl<'g> Game<'g> for PacCrabGame<'g> {
     ) -> Option<()> {
         let mut oam = unmanaged.iter();
 
-        for (location, sprite) in self
-            .world
-            .components::<(&LocationComponent, &SpriteComponent)>()
-        {
+        for (location, sprite) in self.world.components::<(&Transform, &SpriteComponent)>() {
             let mut object = ObjectUnmanaged::new(
                 sprite_loader.get_vram_sprite(sprite.tag.tag().sprite(sprite.frame.into())),
             );
             object
-                .set_position((location.location + sprite.offset).floor())
+                .set_position((location.position + sprite.offset).floor())
                 .show();
             oam.next()?.set(&object);
         }
@@ -224,3 +254,52 @@ impl<'g> Game<'g> for PacCrabGame<'g> {
         Some(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use agb::fixnum::{num, Vector2D};
+    use crabioware_core::physics::{Collider, TileGrid, Transform};
+    use crabioware_core::types::{Number, Rect};
+
+    use super::steer;
+    use crate::components::Direction;
+
+    // A corridor along row 1, crossed by one up column 5
+    struct Crossroads;
+    impl TileGrid for Crossroads {
+        fn tile_size(&self) -> i32 {
+            8
+        }
+
+        fn is_solid(&self, tile: Vector2D<i32>) -> bool {
+            tile.y != 1 && tile.x != 5
+        }
+    }
+
+    #[test_case]
+    fn test_turn_into_corridor(_gba: &mut agb::Gba) {
+        let collider = Collider::new(Rect::new(
+            Vector2D::new(num!(-4.), num!(-4.)),
+            Vector2D::new(num!(8.), num!(8.)),
+        ));
+        let speed = Vector2D::new(num!(0.5), num!(0.5));
+        let mut transform = Transform::at(Vector2D::new(num!(20.), num!(12.)));
+        let mut direction = Direction::RIGHT;
+
+        // UP is held well before the crab reaches the corridor, so it keeps
+        // going right until it lines up with it, then turns
+        for _ in 0..60 {
+            steer(
+                &Crossroads,
+                &mut transform,
+                &collider,
+                speed,
+                &mut direction,
+                Some(Direction::UP),
+            );
+        }
+        assert_eq!(direction, Direction::UP);
+        assert_eq!(transform.position.x, Number::new(44));
+        assert_eq!(transform.position.y, num!(6.));
+    }
+}
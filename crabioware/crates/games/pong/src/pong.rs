@@ -18,17 +18,17 @@ use alloc::vec::Vec;
 
 use crabioware_core::games::{Game, GameDifficulty};
 use crabioware_core::graphics::{GraphicsResource, Mode1TileMap, TileMapResource, TileMode};
-use crabioware_core::physics::Intersects;
-use crabioware_core::types::VecMath;
-use crabioware_core::types::{Number, Rect, RectMath, Vector2D};
+use crabioware_core::physics::{
+    resolve, system_integrate, system_sweep, Collider, CollisionEvent, Intersects, Kinematic,
+    MaxSpeed, RigidBody, Transform, Velocity,
+};
+use crabioware_core::types::{Number, Rect, Vector2D};
 use crabioware_core::{
-    ecs::{EntityId, World},
+    ecs::{Bundle, EntityId, With, Without, World},
     games::{GameState, Games},
 };
 
-use crate::components::{
-    CollisionComponent, LocationComponent, MaxSpeed, SpriteComponent, VelocityComponent,
-};
+use crate::components::{BallComponent, SpriteComponent};
 use crate::graphics::SpriteTag;
 
 // FIXME: keep score
@@ -92,14 +92,18 @@ impl OpponentResource {
     }
 }
 
+#[derive(Bundle)]
 struct Ball {
+    ball: BallComponent,
     sprite: SpriteComponent,
-    location: LocationComponent,
-    velocity: VelocityComponent,
-    collision: CollisionComponent,
+    transform: Transform,
+    velocity: Velocity,
+    max_speed: MaxSpeed,
+    body: RigidBody,
+    collider: Collider,
 }
 impl Ball {
-    fn new(side: &Side, rng: &mut RandomNumberGenerator) -> Self {
+    fn new(side: &Side, max_speed: MaxSpeed, rng: &mut RandomNumberGenerator) -> Self {
         let sprite = SpriteComponent {
             tag: SpriteTag::Ball,
             offset: Default::default(),
@@ -115,41 +119,37 @@ impl Ball {
         ) / num!(10.);
 
         Self {
+            ball: BallComponent,
             sprite,
-            location: LocationComponent::centered(),
-            velocity: VelocityComponent {
+            transform: Transform::centered(),
+            velocity: Velocity {
                 velocity,
                 acceleration: Vector2D::default(),
                 rotation: num!(0.01),
             },
-            collision: CollisionComponent {
-                collision: Rect::new(
-                    Vector2D {
-                        x: num!(4.0),
-                        y: num!(4.0),
-                    },
-                    Vector2D {
-                        x: num!(8.),
-                        y: num!(8.),
-                    },
-                ),
-                bounce: num!(0.9),
+            max_speed,
+            body: RigidBody {
                 inv_mass: num!(1.),
+                bounce: num!(0.9),
+                friction: num!(0.),
             },
+            collider: Collider::new(Rect::new(
+                Vector2D {
+                    x: num!(4.0),
+                    y: num!(4.0),
+                },
+                Vector2D {
+                    x: num!(8.),
+                    y: num!(8.),
+                },
+            ))
+            // Fast balls could otherwise pass through thin paddles
+            .with_ccd(),
         }
     }
-
-    pub fn create(self, world: &mut World) -> EntityId {
-        world
-            .create()
-            .with(self.sprite)
-            .with(self.location)
-            .with(self.velocity)
-            .with(self.collision)
-            .build()
-    }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Side {
     LEFT,
     RIGHT,
@@ -163,11 +163,21 @@ impl Side {
     }
 }
 
+// A ball went out of bounds, scoring a point for one side
+struct ScoreEvent {
+    ball: EntityId,
+    scorer: Side,
+}
+
+#[derive(Bundle)]
 struct Paddle {
+    // Paddles follow input (or the CPU), not `system_integrate`
+    kinematic: Kinematic,
     sprite: SpriteComponent,
-    location: LocationComponent,
-    velocity: VelocityComponent,
-    collision: CollisionComponent,
+    transform: Transform,
+    velocity: Velocity,
+    body: RigidBody,
+    collider: Collider,
 }
 impl Paddle {
     fn new(side: Side, y_velocity: Number) -> Self {
@@ -177,20 +187,18 @@ impl Paddle {
         };
         let y_start: Number = num!(0.25) * GBA_HEIGHT;
         Self {
+            kinematic: Kinematic,
             // paddle mid
             sprite: SpriteComponent {
                 tag: SpriteTag::Paddle,
                 offset: Vector2D::default(),
                 frame: 0,
             },
-            location: LocationComponent {
-                position: Vector2D {
-                    x: x_start,
-                    y: y_start,
-                },
-                angle: num!(0.),
-            },
-            velocity: VelocityComponent {
+            transform: Transform::at(Vector2D {
+                x: x_start,
+                y: y_start,
+            }),
+            velocity: Velocity {
                 velocity: Vector2D {
                     x: num!(0.),
                     y: y_velocity,
@@ -201,32 +209,23 @@ impl Paddle {
                 },
                 rotation: num!(0.0),
             },
-            collision: CollisionComponent {
-                collision: Rect::new(
-                    Vector2D {
-                        x: num!(3.),
-                        y: num!(0.),
-                    },
-                    Vector2D {
-                        x: num!(10.),
-                        y: num!(16.),
-                    },
-                ),
-                bounce: num!(1.0),
+            body: RigidBody {
                 inv_mass: num!(1e-3),
+                bounce: num!(1.0),
+                friction: num!(0.),
             },
+            collider: Collider::new(Rect::new(
+                Vector2D {
+                    x: num!(3.),
+                    y: num!(0.),
+                },
+                Vector2D {
+                    x: num!(10.),
+                    y: num!(16.),
+                },
+            )),
         }
     }
-
-    pub fn create(self, world: &mut World) -> EntityId {
-        world
-            .create()
-            .with(self.sprite)
-            .with(self.location)
-            .with(self.velocity)
-            .with(self.collision)
-            .build()
-    }
 }
 
 // TODO: add a "render cache" that helps us disconnect object setup and render
@@ -236,7 +235,6 @@ pub struct PongGame<'g> {
     game_rng: RandomNumberGenerator,
     player: EntityId,
     opponent: EntityId,
-    balls: Vec<EntityId>,
     opponent_state: OpponentResource,
     game_state: GameStateResource,
     tiles: Option<Mode1TileMap<'g>>,
@@ -252,29 +250,33 @@ impl<'g> PongGame<'g> {
 
         let mut world = World::new();
         world.register_component::<SpriteComponent>();
-        world.register_component::<LocationComponent>();
-        world.register_component::<VelocityComponent>();
-        world.register_component::<CollisionComponent>();
+        world.register_component::<Transform>();
+        world.register_component::<Velocity>();
+        world.register_component::<RigidBody>();
+        world.register_component::<Collider>();
+        world.register_component::<BallComponent>();
+        world.add_event::<CollisionEvent>();
+        world.add_event::<ScoreEvent>();
 
-        let player = Paddle::new(Side::LEFT, num!(0.)).create(&mut world);
-        let opponent = Paddle::new(Side::RIGHT, num!(1.)).create(&mut world);
+        let player = world.spawn(Paddle::new(Side::LEFT, num!(0.)));
+        let opponent = world.spawn(Paddle::new(Side::RIGHT, num!(1.)));
 
         let mut game_state = GameStateResource::new(difficulty);
 
-        let balls: Vec<EntityId> = (0..2)
-            .map(|_| {
-                let ball = Ball::new(&game_state.spawn, &mut game_rng).create(&mut world);
-                game_state.spawn = game_state.spawn.next();
-                ball
-            })
-            .collect();
+        for _ in 0..2 {
+            world.spawn(Ball::new(
+                &game_state.spawn,
+                game_state.max_speed,
+                &mut game_rng,
+            ));
+            game_state.spawn = game_state.spawn.next();
+        }
 
         Self {
             world,
             game_rng,
             player,
             opponent,
-            balls,
             opponent_state: OpponentResource::default(),
             game_state,
             tiles: None,
@@ -282,11 +284,9 @@ impl<'g> PongGame<'g> {
     }
 
     fn system_player(&self, time: i32, buttons: &ButtonController) {
-        let (mut location, mut velocity, collision) = *self.world.entry::<(
-            &mut LocationComponent,
-            &mut VelocityComponent,
-            &CollisionComponent,
-        )>(&self.player);
+        let (mut location, mut velocity, collision) =
+            self.world
+                .entry::<(&mut Transform, &mut Velocity, &Collider)>(&self.player);
 
         match buttons.y_tri() {
             Tri::Positive => {
@@ -314,19 +314,17 @@ impl<'g> PongGame<'g> {
 
     fn system_cpu_acquire_target(
         &self,
-        paddle_location: &LocationComponent,
+        paddle_location: &Transform,
         time: i32,
     ) -> (Option<EntityId>, Number) {
-        let balls = self.world.entries::<(
-            EntityId,
-            &LocationComponent,
-            &VelocityComponent,
-            &CollisionComponent,
-        )>(&self.balls);
-
         // 1. Detect incoming ball(s) moving towards paddle
         let mut incoming = Vec::<(Number, EntityId, Vector2D<Number>, Vector2D<Number>)>::new();
-        for (entity, location, velocity, collision) in balls {
+        for (entity, location, velocity, collision) in
+            self.world
+                .query::<(EntityId, &Transform, &Velocity, &Collider), _>(
+                    &With::<BallComponent>::new(),
+                )
+        {
             let delta = paddle_location.position.x - location.position.x;
             let eta = if velocity.velocity.x != num!(0.) {
                 delta / velocity.velocity.x
@@ -337,7 +335,7 @@ impl<'g> PongGame<'g> {
                 incoming.push((
                     eta,
                     entity,
-                    location.position + collision.collision.size / num!(2.),
+                    location.position + collision.rect.size / num!(2.),
                     velocity.velocity,
                 ))
             }
@@ -364,17 +362,15 @@ impl<'g> PongGame<'g> {
     fn system_cpu_track_target(
         &self,
         target: EntityId,
-        paddle_location: &LocationComponent,
+        paddle_location: &Transform,
         time: i32,
     ) -> (Option<EntityId>, bool, Number) {
         // Make sure ball is alive
         if self.world.is_alive(&target) {
             // FIXME: find current y position
-            let (ball_location, ball_velocity, ball_collision) = *self.world.entry::<(
-                &mut LocationComponent,
-                &mut VelocityComponent,
-                &CollisionComponent,
-            )>(&target);
+            let (ball_location, ball_velocity, ball_collision) =
+                self.world
+                    .entry::<(&mut Transform, &mut Velocity, &Collider)>(&target);
 
             // FIXME: check frames to impact against delta_y distance.. we might not make it!
 
@@ -384,7 +380,7 @@ impl<'g> PongGame<'g> {
                 let delta = paddle_location.position.x - ball_location.position.x;
                 if delta * ball_velocity.velocity.x > num!(0.) {
                     let target_y = ball_location.position.y
-                        + ball_collision.collision.size.y
+                        + ball_collision.rect.size.y
                         + ball_velocity.velocity.y * time;
                     return (Some(target), true, target_y);
                 }
@@ -399,11 +395,8 @@ impl<'g> PongGame<'g> {
     fn system_cpu_paddle(&mut self, entity: EntityId, time: i32) {
         // FIXME: increment opponent logic ~ GameDifficulty
         let (mut paddle_location, mut paddle_velocity, paddle_collision) =
-            *self.world.entry::<(
-                &mut LocationComponent,
-                &mut VelocityComponent,
-                &CollisionComponent,
-            )>(&entity);
+            self.world
+                .entry::<(&mut Transform, &mut Velocity, &Collider)>(&entity);
 
         let (target, target_y) = match self.opponent_state.target {
             Some(target) => {
@@ -427,7 +420,7 @@ impl<'g> PongGame<'g> {
         self.opponent_state.target = target;
 
         let delta_y =
-            target_y - paddle_location.position.y - paddle_collision.collision.size.y / num!(2.);
+            target_y - paddle_location.position.y - paddle_collision.rect.size.y / num!(2.);
 
         let zero = num!(0.);
         let new_velocity_y = if delta_y < zero {
@@ -438,7 +431,7 @@ impl<'g> PongGame<'g> {
             0.into()
         };
         paddle_velocity.velocity.y = new_velocity_y;
-        paddle_velocity.clamp_velocity(&self.game_state.max_speed);
+        paddle_velocity.clamp(&self.game_state.max_speed);
 
         // Move min(distance to target, velocity * time)
         let move_range_y = paddle_velocity.velocity.y * time;
@@ -457,120 +450,124 @@ impl<'g> PongGame<'g> {
 
     fn clamp_paddle(
         &self,
-        location: &mut LocationComponent,
-        velocity: &mut VelocityComponent,
-        collision: &CollisionComponent,
+        location: &mut Transform,
+        velocity: &mut Velocity,
+        collision: &Collider,
     ) {
         let zero = num!(0.);
         if location.position.y < zero {
             location.position.y = zero;
             velocity.velocity.y = zero;
-        } else if location.position.y + collision.collision.size.y > GBA_HEIGHT.into() {
-            location.position.y = Number::new(GBA_HEIGHT) - collision.collision.size.y;
+        } else if location.position.y + collision.rect.size.y > GBA_HEIGHT.into() {
+            location.position.y = Number::new(GBA_HEIGHT) - collision.rect.size.y;
             velocity.velocity.y = zero;
         }
     }
 
-    fn system_balls(&self, time: i32) {
+    fn system_collision(&self, _: i32) {
+        // We're checking intersection after movement, not along the
+        // trajectory. Balls are swept beforehand (`system_sweep`) so they
+        // can't phase through paddles, and this unsticks anything that
+        // still ends up overlapping
+
+        // Balls can hit each other and the paddles, but paddles never meet.
+        // Paddles are spawned before balls, so keep them first in each pair
+        // to match the order `separation` has always been given them in.
+        type Body<'a> = (
+            EntityId,
+            &'a mut Transform,
+            &'a mut Velocity,
+            &'a RigidBody,
+            &'a Collider,
+        );
+        let balls = With::<BallComponent>::new();
+        let paddles = Without::<BallComponent>::new();
         let iter = self
             .world
-            .entries::<(&mut LocationComponent, &VelocityComponent)>(&self.balls);
-        for (mut location, velocity) in iter {
-            location.position += velocity.velocity * time;
-            location.angle += velocity.rotation * time;
-        }
-    }
-
-    fn system_collision(&self, _: i32) {
-        // We're checking intersection based on potential movement, not
-        // trajectory. If entities are moving really fast we might
-        // have them phase through each other, but otherwise this is
-        // a quicker way of checking collisions than continuous collision detection
-
-        let iter = self.world.combinations::<(
-            &mut LocationComponent,
-            &mut VelocityComponent,
-            &CollisionComponent,
-        )>();
+            .query_combinations::<Body, _>(&balls)
+            .chain(self.world.query_pairs::<Body, Body, _, _>(&paddles, &balls));
 
         for (
-            (mut location_a, mut velocity_a, collision_a),
-            (mut location_b, mut velocity_b, collision_b),
+            (entity_a, mut location_a, mut velocity_a, body_a, collision_a),
+            (entity_b, mut location_b, mut velocity_b, body_b, collision_b),
         ) in iter
         {
-            let collision_box_a = collision_a.collision.translate(location_a.position);
-            let collision_box_b = collision_b.collision.translate(location_b.position);
+            if !collision_a.collides_with(&collision_b) {
+                continue;
+            }
+            let collision_box_a = collision_a.bounds(&location_a);
+            let collision_box_b = collision_b.bounds(&location_b);
 
             if let Some(collided) = collision_box_a.separation(&collision_box_b) {
-                // Unstick
-                let inv_masses = collision_a.inv_mass + collision_b.inv_mass;
-                let delta_a = collided.separation * collision_a.inv_mass / inv_masses;
-                let delta_b = collided.separation * collision_b.inv_mass / inv_masses;
-                location_a.position -= delta_a;
-                location_b.position += delta_b;
-
-                // Resolve collision
-                let elasticity = collision_a.bounce.min(collision_b.bounce);
-                let relative_velocity = velocity_a.velocity - velocity_b.velocity;
-                let relative_velocity_norm = relative_velocity.dot(collided.normal);
-
-                // Don't update if already moving away
-                if relative_velocity_norm > num!(0.) {
-                    // FIXME: missing representation of tangent impulse + friction info
-                    let impulse = -(num!(1.) + elasticity) * relative_velocity_norm / inv_masses;
-
-                    velocity_a.velocity += collided.normal * impulse * collision_a.inv_mass;
-                    velocity_b.velocity -= collided.normal * impulse * collision_b.inv_mass;
-
-                    velocity_a.clamp_velocity(&self.game_state.max_speed);
-                    velocity_b.clamp_velocity(&self.game_state.max_speed);
+                self.world.send_event(CollisionEvent {
+                    a: entity_a,
+                    b: entity_b,
+                    normal: collided.normal,
+                });
+
+                // Fully unstick overlapping bodies, with no slop
+                let impulse = resolve(
+                    &collided,
+                    num!(0.),
+                    (&mut location_a, &mut velocity_a, &body_a),
+                    (&mut location_b, &mut velocity_b, &body_b),
+                );
+                if impulse {
+                    velocity_a.clamp(&self.game_state.max_speed);
+                    velocity_b.clamp(&self.game_state.max_speed);
                 }
             }
         }
     }
 
-    fn system_bounds(&mut self, _: i32) {
-        let iter = self.world.entries::<(
-            EntityId,
-            &LocationComponent,
-            &mut VelocityComponent,
-            &CollisionComponent,
-        )>(&self.balls);
-
+    fn system_bounds(&self, _: i32) {
         let zero: Number = num!(0.);
-        let mut scored = Vec::<EntityId>::new();
-        for (entity, location, mut velocity, collision) in iter {
+        for (entity, location, mut velocity, collision) in
+            self.world
+                .query::<(EntityId, &Transform, &mut Velocity, &Collider), _>(
+                    &With::<BallComponent>::new(),
+                )
+        {
             // Bounce off top/bottom
             if (location.position.y < zero && velocity.velocity.y < zero)
-                || (location.position.y + collision.collision.size.y > GBA_HEIGHT.into()
+                || (location.position.y + collision.rect.size.y > GBA_HEIGHT.into()
                     && velocity.velocity.y > zero)
             {
                 velocity.velocity.y *= num!(-1.0)
             }
 
             if location.position.x < zero && velocity.velocity.x < zero {
-                self.game_state.opponent_score += 1;
-                scored.push(entity);
-            }
-            if location.position.x + collision.collision.size.x > GBA_WIDTH.into()
+                self.world.send_event(ScoreEvent {
+                    ball: entity,
+                    scorer: Side::RIGHT,
+                });
+            } else if location.position.x + collision.rect.size.x > GBA_WIDTH.into()
                 && velocity.velocity.x > num!(0.)
             {
-                self.game_state.player_score += 1;
-                scored.push(entity);
+                self.world.send_event(ScoreEvent {
+                    ball: entity,
+                    scorer: Side::LEFT,
+                });
             }
         }
-
-        self.system_ball_scored(scored);
     }
 
-    fn system_ball_scored(&mut self, balls: Vec<EntityId>) {
-        self.balls.retain(|b| !balls.contains(b));
-        for ball in balls {
-            self.world.destroy(&ball);
-            let new_ball =
-                Ball::new(&self.game_state.spawn, &mut self.game_rng).create(&mut self.world);
+    fn system_score(&mut self) {
+        let mut commands = self.world.commands();
+        for event in self.world.events::<ScoreEvent>().iter() {
+            match event.scorer {
+                Side::LEFT => self.game_state.player_score += 1,
+                Side::RIGHT => self.game_state.opponent_score += 1,
+            }
+
+            // Replace the ball that scored
+            let ball = Ball::new(
+                &self.game_state.spawn,
+                self.game_state.max_speed,
+                &mut self.game_rng,
+            );
             self.game_state.spawn = self.game_state.spawn.next();
-            self.balls.push(new_ball);
+            commands.destroy(event.ball).spawn_bundle(ball);
         }
     }
 
@@ -635,11 +632,16 @@ impl<'g> Game<'g> for PongGame<'g> {
     }
 
     fn advance(&mut self, time: i32, buttons: &ButtonController) -> GameState {
+        self.world.advance_tick();
+        self.world.clear_events();
         self.system_player(time, &buttons);
-        self.system_balls(time);
+        system_sweep(&mut self.world);
+        system_integrate(&mut self.world);
         self.system_cpu_paddle(self.opponent, time);
         self.system_collision(time);
         self.system_bounds(time);
+        self.system_score();
+        self.world.apply_commands();
         self.game_state.game_state()
     }
 
@@ -667,7 +669,7 @@ impl<'g> Game<'g> for PongGame<'g> {
 
         for (location, sprite) in self
             .world
-            .components::<(&LocationComponent, &mut SpriteComponent)>()
+            .components::<(&Transform, &mut SpriteComponent)>()
         {
             let affine = AffineMatrixInstance::new(
                 AffineMatrix::from_rotation(location.angle).to_object_wrapping(),
@@ -12,14 +12,13 @@ static TILE_HEIGHT: i32 = 16;
 pub static N_TILES_WIDE: i16 = (GBA_WIDTH / TILE_WIDTH) as i16;
 pub static N_TILES_TALL: i16 = (GBA_HEIGHT / TILE_HEIGHT) as i16;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
 pub enum DirectionComponent {
     UP,
     DOWN,
     LEFT,
     RIGHT,
 }
-impl Component for DirectionComponent {}
 impl DirectionComponent {
     pub fn dx(&self) -> i16 {
         match self {
@@ -48,12 +47,21 @@ impl DirectionComponent {
     }
 }
 
-#[derive(Clone, Copy, Default)]
+// Marks the snake's head
+#[derive(Clone, Copy, Default, Component)]
+#[component(storage = "sparse")]
+pub struct HeadComponent;
+
+// Marks a berry for the snake to eat
+#[derive(Clone, Copy, Default, Component)]
+#[component(storage = "sparse")]
+pub struct BerryComponent;
+
+#[derive(Clone, Copy, Default, Component)]
 pub struct TileComponent {
     pub x: i16,
     pub y: i16,
 }
-impl Component for TileComponent {}
 impl TileComponent {
     pub fn equals(&self, other: &Self) -> bool {
         self.x == other.x && self.y == other.y
@@ -79,7 +87,7 @@ impl TileComponent {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Component)]
 pub struct SpriteComponent {
     pub tag: SpriteTag,
     pub frame: u8,
@@ -88,4 +96,3 @@ pub struct SpriteComponent {
     // FIXME: add priority back when we have a background layer
     // pub priority: Priority,
 }
-impl Component for SpriteComponent {}
@@ -10,16 +10,20 @@ use alloc::vec;
 use alloc::vec::Vec;
 
 use crabioware_core::{
-    ecs::{EntityId, World},
+    ecs::{Bundle, Children, EntityId, RunCondition, Schedule, Stage, With, Without, World},
     games::{Game, GameDifficulty, GameState, Games},
     graphics::{GraphicsResource, Mode0TileMap, TileMapResource, TileMode},
 };
 
-use super::components::{DirectionComponent, SpriteComponent, TileComponent};
+use super::components::{
+    BerryComponent, DirectionComponent, HeadComponent, SpriteComponent, TileComponent,
+};
 use super::components::{N_TILES_TALL, N_TILES_WIDE};
 use super::graphics::SpriteTag;
 
+#[derive(Bundle)]
 struct Berry {
+    berry: BerryComponent,
     tile: TileComponent,
     sprite: SpriteComponent,
 }
@@ -27,6 +31,7 @@ impl Berry {
     pub fn random(rng: &mut RandomNumberGenerator) -> Self {
         let tile = TileComponent::random(rng);
         Berry {
+            berry: BerryComponent,
             tile,
             sprite: SpriteComponent {
                 tag: SpriteTag::Berry,
@@ -34,12 +39,9 @@ impl Berry {
             },
         }
     }
-
-    pub fn create(self, world: &mut World) -> EntityId {
-        world.create().with(self.tile).with(self.sprite).build()
-    }
 }
 
+#[derive(Bundle)]
 struct Body {
     tile: TileComponent,
     sprite: SpriteComponent,
@@ -54,17 +56,13 @@ impl Body {
             },
         }
     }
-
-    pub fn create(self, world: &mut World) -> EntityId {
-        world.create().with(self.tile).with(self.sprite).build()
-    }
 }
 
 struct GameStateResource {
-    time: u32,
     speed: u8,
     score: u8,
     max_score: u8,
+    state: GameState,
 }
 impl GameStateResource {
     fn new(difficulty: &GameDifficulty) -> GameStateResource {
@@ -79,23 +77,181 @@ impl GameStateResource {
             GameDifficulty::HARD => 15,
         };
         GameStateResource {
-            time: 0,
             speed,
             score: 0,
             max_score,
+            state: GameState::Running(Games::Snake),
         }
     }
 }
 
-pub struct SnakeGame<'g> {
-    world: World,
-    rng: RandomNumberGenerator,
+// Buttons pressed this frame
+struct InputResource {
+    x: Tri,
+    y: Tri,
+}
+
+struct SnakeResource {
     // Store head direction and commit upon movement
     head_direction: DirectionComponent,
-    // Store entities separately, sort of like a hacky archetype
-    body: Vec<EntityId>,
-    berries: Vec<EntityId>,
-    game_state: GameStateResource,
+    head: EntityId,
+    // Tile the head moves into this step, and how many berries it found there
+    next_head: TileComponent,
+    eaten: u8,
+}
+
+fn system_controller(world: &mut World) {
+    let input = world.resource::<InputResource>().unwrap();
+    let mut snake = world.resource_mut::<SnakeResource>().unwrap();
+    let direction = *world.entry::<&DirectionComponent>(&snake.head);
+    match input.x {
+        Tri::Positive => {
+            if direction != DirectionComponent::LEFT {
+                snake.head_direction = DirectionComponent::RIGHT;
+            }
+        }
+        Tri::Negative => {
+            if direction != DirectionComponent::RIGHT {
+                snake.head_direction = DirectionComponent::LEFT;
+            }
+        }
+        _ => {}
+    };
+    match input.y {
+        Tri::Positive => {
+            if direction != DirectionComponent::UP {
+                snake.head_direction = DirectionComponent::DOWN;
+            }
+        }
+        Tri::Negative => {
+            if direction != DirectionComponent::DOWN {
+                snake.head_direction = DirectionComponent::UP;
+            }
+        }
+        _ => {}
+    };
+}
+
+fn system_head(world: &mut World) {
+    // FIXME: snake sprite changes with direction
+    let mut snake = world.resource_mut::<SnakeResource>().unwrap();
+    let (mut direction, tile) =
+        world.entry::<(&mut DirectionComponent, &TileComponent)>(&snake.head);
+
+    snake.next_head = TileComponent {
+        x: tile.x.wrapping_add(snake.head_direction.dx()),
+        y: tile.y.wrapping_add(snake.head_direction.dy()),
+    };
+    direction.clone_from(&snake.head_direction);
+}
+
+// Check if head has eaten a berry, storing nutritional content of berries eaten
+fn system_eat_berry(world: &mut World) {
+    let mut snake = world.resource_mut::<SnakeResource>().unwrap();
+    let mut commands = world.commands();
+
+    let mut eaten: u8 = 0;
+    for (berry, berry_tile) in
+        world.query::<(EntityId, &TileComponent), _>(&With::<BerryComponent>::new())
+    {
+        if berry_tile.equals(&snake.next_head) {
+            eaten += 1;
+            commands.destroy(berry);
+        }
+    }
+    snake.eaten = eaten;
+}
+
+fn system_spawn_berry(world: &mut World) {
+    // Spawn berries?
+    if world
+        .query::<EntityId, _>(&With::<BerryComponent>::new())
+        .next()
+        .is_none()
+    {
+        // FIXME: berries spawn randomly where snake isn't
+        let berry = Berry::random(&mut world.resource_mut::<RandomNumberGenerator>().unwrap());
+        world.spawn(berry);
+    }
+}
+
+// The head, then its body segments in order to the tail
+fn segments(world: &World, head: EntityId) -> Vec<EntityId> {
+    let mut segments = vec![head];
+    if let Ok(Some(body)) = world.get::<Option<&Children>>(&head) {
+        segments.extend(body.iter());
+    }
+    segments
+}
+
+fn system_body(world: &mut World) {
+    let (head, next_head, berries_eaten) = {
+        let snake = world.resource::<SnakeResource>().unwrap();
+        (snake.head, snake.next_head, snake.eaten)
+    };
+    // Add new tail segment(s)
+    if berries_eaten > 0 {
+        let tail = *segments(world, head).last().unwrap();
+        let tail_tile = *world.entry::<&TileComponent>(&tail);
+        // Segments are children of the head, in order from head to tail
+        for _ in 0..berries_eaten {
+            world
+                .create()
+                .with_bundle(Body::new(tail_tile))
+                .child_of(head)
+                .build();
+        }
+    }
+
+    // Move the snake body up 1 segment
+    let body = segments(world, head);
+    for (i, body_from_tail) in body.iter().enumerate().rev() {
+        let mut tile_body_from_tail = world.entry::<&mut TileComponent>(body_from_tail);
+        if i == 0 {
+            // Move head to next tile
+            tile_body_from_tail.x = next_head.x;
+            tile_body_from_tail.y = next_head.y;
+        } else {
+            // Or move tail one segment closer to head
+            let tile_body_from_head = world.entry::<&TileComponent>(&body[i - 1]);
+            tile_body_from_tail.x = tile_body_from_head.x;
+            tile_body_from_tail.y = tile_body_from_head.y;
+        }
+    }
+}
+
+fn system_collide(world: &mut World) {
+    let head = world.resource::<SnakeResource>().unwrap().next_head;
+
+    // Head hit a wall
+    let hit_wall = head.hit_wall();
+    // Snake bit itself
+    let bit_self = world
+        .query::<&TileComponent, _>(&(
+            Without::<HeadComponent>::new(),
+            Without::<BerryComponent>::new(),
+        ))
+        .any(|body| head.equals(&body));
+
+    if hit_wall || bit_self {
+        world.resource_mut::<GameStateResource>().unwrap().state = GameState::GameOver;
+    }
+}
+
+fn system_score(world: &mut World) {
+    let eaten = world.resource::<SnakeResource>().unwrap().eaten;
+    let mut game_state = world.resource_mut::<GameStateResource>().unwrap();
+    if let GameState::Running(game) = game_state.state {
+        game_state.score += eaten;
+        if game_state.score > game_state.max_score {
+            game_state.state = GameState::Win(game);
+        }
+    }
+}
+
+pub struct SnakeGame<'g> {
+    world: World,
+    schedule: Schedule,
     // background tilemap
     tiles: Option<Mode0TileMap<'g>>,
 }
@@ -105,6 +261,8 @@ impl<'g> SnakeGame<'g> {
         world.register_component::<DirectionComponent>();
         world.register_component::<TileComponent>();
         world.register_component::<SpriteComponent>();
+        world.register_component::<HeadComponent>();
+        world.register_component::<BerryComponent>();
 
         let mut game_rng = RandomNumberGenerator::new_with_seed([
             rng.gen().abs() as u32,
@@ -115,154 +273,61 @@ impl<'g> SnakeGame<'g> {
 
         let head_tile_component = TileComponent::random(&mut game_rng);
         let head_direction = DirectionComponent::random(&mut game_rng);
-        let head = world
-            .create()
-            .with(head_tile_component)
-            .with(head_direction)
-            .with(SpriteComponent {
+        let head = world.spawn((
+            HeadComponent,
+            head_tile_component,
+            head_direction,
+            SpriteComponent {
                 tag: SpriteTag::Snake,
                 frame: 0,
-            })
-            .build();
-        let body = vec![head];
-
-        let berry = Berry::random(&mut game_rng).create(&mut world);
-        let berries = vec![berry];
-
-        SnakeGame {
-            world,
-            rng: game_rng,
-            head_direction,
-            body,
-            berries,
-            game_state: GameStateResource::new(difficulty),
-            tiles: None,
-        }
-    }
-
-    fn system_controller(&mut self, buttons: &ButtonController) {
-        let direction = **self.world.entry::<&DirectionComponent>(&self.body[0]);
-        match buttons.x_tri() {
-            Tri::Positive => {
-                if direction != DirectionComponent::LEFT {
-                    self.head_direction = DirectionComponent::RIGHT;
-                }
-            }
-            Tri::Negative => {
-                if direction != DirectionComponent::RIGHT {
-                    self.head_direction = DirectionComponent::LEFT;
-                }
-            }
-            _ => {}
-        };
-        match buttons.y_tri() {
-            Tri::Positive => {
-                if direction != DirectionComponent::UP {
-                    self.head_direction = DirectionComponent::DOWN;
-                }
-            }
-            Tri::Negative => {
-                if direction != DirectionComponent::DOWN {
-                    self.head_direction = DirectionComponent::UP;
-                }
-            }
-            _ => {}
-        };
-    }
+            },
+        ));
 
-    fn system_head(&self, time: i32) -> TileComponent {
-        // FIXME: snake sprite changes with direction
-        let (mut direction, tile) = *self
-            .world
-            .entry::<(&mut DirectionComponent, &TileComponent)>(&self.body[0]);
+        world.spawn(Berry::random(&mut game_rng));
 
-        let new_tile = TileComponent {
-            x: tile.x.wrapping_add(self.head_direction.dx() * time as i16),
-            y: tile.y.wrapping_add(self.head_direction.dy() * time as i16),
-        };
-        direction.clone_from(&self.head_direction);
-        new_tile
-    }
-
-    fn system_spawn_berry(&mut self) {
-        // Spawn berries?
-        if self.berries.len() == 0 {
-            // FIXME: berries spawn randomly where snake isn't
-            let berry = Berry::random(&mut self.rng).create(&mut self.world);
-            self.berries.push(berry);
-        }
-    }
+        let game_state = GameStateResource::new(difficulty);
+        // Only advance every FPS / speed ~+ 1/sec on easy
+        let every_step = RunCondition::EveryNFrames(game_state.speed.into());
 
-    // Check if head has eaten a berry, returning nutritional content of berry
-    fn system_eat_berry(&mut self, head_tile: &TileComponent) -> u8 {
-        let mut eaten: u8 = 0;
-        self.berries.retain(|&berry| {
-            let berry_tile = self.world.entry::<&TileComponent>(&berry).clone();
-            if berry_tile.equals(&head_tile) {
-                eaten += 1;
-                self.world.destroy(&berry);
-                return false;
-            }
-            return true;
+        world.insert_resource(SnakeResource {
+            head_direction,
+            head,
+            next_head: head_tile_component,
+            eaten: 0,
         });
-        eaten
-    }
-
-    fn system_body(&mut self, next_head: &TileComponent, berries_eaten: u8) {
-        // Store original body length
-        let body_length = self.body.len();
-
-        // Add new tail segment(s)
-        if berries_eaten > 0 {
-            let tail = self
-                .world
-                .entry::<&TileComponent>(&self.body[body_length - 1])
-                .clone();
-            for _ in 0..berries_eaten {
-                let new_tail = Body::new(tail.clone()).create(&mut self.world);
-                self.body.push(new_tail);
-            }
-        }
-
-        // Move the snake body up 1 segment
-        for (i, body_from_tail) in self.body.iter().enumerate().rev() {
-            let mut tile_body_from_tail = self.world.entry::<&mut TileComponent>(body_from_tail);
-            if i == 0 {
-                // Move head to next tile
-                tile_body_from_tail.x = next_head.x;
-                tile_body_from_tail.y = next_head.y;
-            } else {
-                // Or move tail one segment closer to head
-                let tile_body_from_head = self.world.entry::<&TileComponent>(&self.body[i - 1]);
-                tile_body_from_tail.x = tile_body_from_head.x;
-                tile_body_from_tail.y = tile_body_from_head.y;
-            }
-        }
-    }
+        world.insert_resource(InputResource {
+            x: Tri::Zero,
+            y: Tri::Zero,
+        });
+        world.insert_resource(game_rng);
+        world.insert_resource(game_state);
+
+        let schedule = Schedule::new()
+            .with_system(Stage::Input, system_controller)
+            .with_system_if(Stage::Simulate, every_step, system_head)
+            .with_system_if(Stage::Simulate, every_step, system_eat_berry)
+            .with_system_if(Stage::Simulate, every_step, system_body)
+            .with_system_if(Stage::Collide, every_step, system_collide)
+            .with_system_if(Stage::Late, every_step, system_score)
+            // Eaten berries are only gone after the earlier stages
+            .with_system_if(Stage::Late, every_step, system_spawn_berry);
 
-    fn system_collide(&mut self, head: &TileComponent) -> GameState {
-        // Head hit a wall
-        if head.hit_wall() {
-            return GameState::GameOver;
-        }
-        // Snake bit itself
-        for body in self.body[1..].iter() {
-            let body_tile = self.world.entry::<&TileComponent>(body);
-            if head.equals(&body_tile) {
-                return GameState::GameOver;
-            }
+        SnakeGame {
+            world,
+            schedule,
+            tiles: None,
         }
-        GameState::Running(Games::Snake)
     }
 
     fn renderer_digits(&self, loader: &mut SpriteLoader, oam: &mut OamIterator) {
         // FIXME: refactor into some commonly useful score screen
         // FIXMEx2: isn't there a background layer for stuff like this?
-        let digits: Vec<u8> = match self.game_state.score {
+        let score = self.world.resource::<GameStateResource>().unwrap().score;
+        let digits: Vec<u8> = match score {
             0 => vec![0u8],
             _ => {
                 let mut digits: Vec<u8> = Vec::new();
-                let mut score_ = self.game_state.score.clone();
+                let mut score_ = score.clone();
                 while score_ != 0 {
                     digits.push(score_ % 10);
                     score_ /= 10;
@@ -303,36 +368,13 @@ impl<'g> Game<'g> for SnakeGame<'g> {
         self.tiles = Some(tiles);
     }
 
-    fn advance(&mut self, time: i32, buttons: &ButtonController) -> GameState {
-        self.game_state.time = self.game_state.time.wrapping_add_signed(time);
-
-        self.system_controller(buttons);
-
-        // Only advance every FPS / speed ~+ 1/sec on easy
-        if self.game_state.time % self.game_state.speed as u32 != 0 {
-            return GameState::Running(Games::Snake);
-        }
-
-        // Move head tile in direction
-        let head_tile = self.system_head(time);
-
-        let eaten = self.system_eat_berry(&head_tile);
-        self.system_spawn_berry();
-
-        self.system_body(&head_tile, eaten);
-
-        let state = self.system_collide(&head_tile);
-        match state {
-            GameState::Running(game) => {
-                self.game_state.score += eaten as u8;
-                if self.game_state.score > self.game_state.max_score {
-                    GameState::Win(game)
-                } else {
-                    state
-                }
-            }
-            _ => state,
-        }
+    fn advance(&mut self, _time: i32, buttons: &ButtonController) -> GameState {
+        self.world.insert_resource(InputResource {
+            x: buttons.x_tri(),
+            y: buttons.y_tri(),
+        });
+        self.schedule.run(&mut self.world);
+        self.world.resource::<GameStateResource>().unwrap().state
     }
 
     fn render(